@@ -2,51 +2,255 @@
 use eframe::egui;
 use egui::Ui;
 
-use std::sync::mpsc;
-use std::thread;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 mod analysis;
-use analysis::ColorPoint;
+use analysis::{ColorCurve, DuplicatePair, Metrics, PrunedColor};
+
+mod batch;
+use batch::{Batch, Export, ImageResult};
+
+mod border;
+use border::{Border, Motif};
+
+mod color;
+
+mod colorway;
+use colorway::{Colorway, Recolor};
 
 mod config;
-use config::Config;
+use config::{Config, PIXELS_PER_DAY_AVG};
+
+mod craft;
+use craft::{Craft, Offset};
+
+mod data;
+
+mod decorations;
+use decorations::{Decorations, PlacedText};
+
+mod delica;
+
+mod embroidery;
+use embroidery::StitchPlan;
+
+mod floss;
+use floss::{Catalog, Floss};
 
 mod input;
 
+mod lettering;
+use lettering::{Alphabet, Lettering, TrueTypeFont, Typeface};
+
+mod mask;
+use mask::Mask;
+
+mod order;
+use order::StitchOrder;
+
+mod progress;
+use progress::Progress;
+
+mod project;
+use project::Project;
+
 mod renderer;
-use renderer::ChartData;
+use renderer::{Chart, ChartData};
+
+mod repeat;
+use repeat::{Repeat, Symmetry, Tile};
+
+mod schedule;
+use schedule::Schedule;
+
+mod stash;
+use stash::Stash;
+
+mod svg;
+
+mod swatches;
+
+mod worker;
+use worker::{AnalysisWorker, Output};
+
+mod yarn;
+
+/// What clicking the chart does
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ChartTool {
+    Inspect,
+    MarkProgress,
+    PlaceText,
+    MarkTile,
+    PaintStitches,
+    Backstitch,
+}
+
+// Pixel art is charted at its own size, so it has to fit the width and height sliders' range.
+// Larger "pixel art" is more likely a photo that happens to be sharp, and would make an unreasonably big chart.
+const MIN_PIXEL_ART_SIZE: usize = 10;
+const MAX_PIXEL_ART_SIZE: usize = 500;
+
+/// The chart size for one stitch per art pixel, if the image looks like pixel art of a size that can be charted.
+fn pixel_art_grid(image: &egui::ColorImage) -> Option<[usize; 2]> {
+    let chart_sizes = MIN_PIXEL_ART_SIZE..=MAX_PIXEL_ART_SIZE;
+    analysis::native_grid(image).filter(|grid| chart_sizes.contains(&grid[0]) && chart_sizes.contains(&grid[1]))
+}
 
 // Squashed together samples to test UI tech
 // https://github.com/emilk/egui/blob/master/crates/egui_demo_lib/src/demo/plot_demo.rs
 pub struct Cross {
     // Visual display
-    image: egui::ColorImage,
+    // Shared with the analysis thread, so it's never copied. image_id changes whenever a new image is loaded.
+    image: Arc<egui::ColorImage>,
+    image_id: u64,
+    image_path: Option<PathBuf>,
     texture: Option<egui::TextureHandle>,
 
+    // Areas of the image that should keep their detail, painted over the thumbnail
+    mask: Mask,
+    mask_texture: Option<egui::TextureHandle>,
+    painting_mask: bool,
+    brush_size: f32,
+
+    // Size of the image in art pixels, if it's small enough to chart one stitch per pixel
+    pixel_grid: Option<[usize; 2]>,
+
     // Analysis subthread
-    process_handle: Option<std::thread::JoinHandle<Vec<ColorPoint>>>,
-    discarded_processes: Vec<std::thread::JoinHandle<Vec<ColorPoint>>>,
-    cancel_sender: Option<mpsc::Sender<bool>>,
-    has_finished: bool,
-    
+    worker: AnalysisWorker,
+
     // Analysis settings
     config: Config,
 
-    // Result
+    // Result, as analyzed and as shown with decorations stitched over it
+    analyzed_chart: Option<ChartData>,
     chart_data: Option<ChartData>,
+    chart: Chart,
+    chart_tool: ChartTool,
+
+    // Text placed on the chart, and the text being set up to place next
+    decorations: Decorations,
+    lettering: Lettering,
+    preview_stitch: Option<usize>,
+
+    // Alternate colorings of the chart, the one shown (the chart's own colors if None), and the next one to add
+    colorways: Vec<Colorway>,
+    shown_colorway: Option<usize>,
+    new_colorway_name: String,
+    new_recolor: Recolor,
+
+    // Machine embroidery stitches for the current chart, once planned, with a drawing of their path
+    stitch_plan: Option<(StitchPlan, egui::TextureHandle)>,
+
+    // Border settings, kept while the border is turned off
+    border: Border,
+
+    // Symmetry for the next tile marked, and the first corner clicked while marking it
+    symmetry: Symmetry,
+    tile_corner: Option<usize>,
+
+    // Catalog code of the floss (or yarn or bead) to paint stitches with
+    paint_floss_code: String,
+
+    // Backstitch floss, the analyzed chart's grid corner the next line starts from, and the corner under the pointer
+    backstitch_floss_code: String,
+    backstitch_start: Option<[usize; 2]>,
+    preview_corner: Option<[usize; 2]>,
+
+    // Batch conversion: where images come from and go, the settings preset (current settings if None),
+    // and the batch running or last run
+    batch_input: String,
+    batch_output: Option<PathBuf>,
+    batch_preset: Option<(String, Config)>,
+    batch_exports: Vec<Export>,
+    batch: Option<Batch>,
+    batch_results: Vec<ImageResult>,
+    batch_message: String,
+
+    // How closely the analyzed chart matches its image. Not available for charts opened from a project.
+    metrics: Option<Metrics>,
+
+    // Colors the analysis dropped for having too few stitches
+    pruned_colors: Vec<PrunedColor>,
+    showing_error_heatmap: bool,
+
+    // Error at a range of color counts, once the user has asked for a suggestion
+    color_curve: Option<ColorCurve>,
+
+    // Suggested stitching order for the current chart, once planned, whether it's drawn on the chart,
+    // and the one color to draw it for (every color if None)
+    stitch_order: Option<StitchOrder>,
+    showing_stitch_order: bool,
+    order_color: Option<usize>,
+
+    // Day-by-day plan for the current chart. Days are numbered from 1, with 0 highlighting nothing.
+    schedule: Option<Schedule>,
+    highlighted_day: usize,
+
+    // Completed stitches
+    progress: Progress,
+
+    // Threads on hand, and the floss being typed in to add to them
+    stash: Stash,
+    new_thread_code: String,
+    new_thread_meters: f32,
 }
 
 impl Default for Cross {
     fn default() -> Self {
         Self {
-            image: egui::ColorImage::default(),
+            image: Arc::default(),
+            image_id: 0,
+            image_path: None,
             texture: None,
-            process_handle: None,
-            cancel_sender: None,
-            has_finished: false,
+            mask: Mask::default(),
+            mask_texture: None,
+            painting_mask: false,
+            brush_size: 0.05,
+            pixel_grid: None,
+            worker: AnalysisWorker::default(),
+            analyzed_chart: None,
             chart_data: None,
+            chart: Chart::default(),
+            chart_tool: ChartTool::Inspect,
+            decorations: Decorations::default(),
+            lettering: Lettering::default(),
+            preview_stitch: None,
+            colorways: vec![],
+            shown_colorway: None,
+            new_colorway_name: String::new(),
+            new_recolor: Recolor::HueRotation { degrees: 180.0 },
+            stitch_plan: None,
+            border: Border::default(),
+            symmetry: Symmetry::RepeatBoth,
+            tile_corner: None,
+            paint_floss_code: "310".to_string(),
+            backstitch_floss_code: "310".to_string(),
+            backstitch_start: None,
+            preview_corner: None,
+            batch_input: String::new(),
+            batch_output: None,
+            batch_preset: None,
+            batch_exports: vec![Export::Project, Export::ChartImage],
+            batch: None,
+            batch_results: vec![],
+            batch_message: String::new(),
+            metrics: None,
+            pruned_colors: vec![],
+            showing_error_heatmap: false,
+            color_curve: None,
+            stitch_order: None,
+            showing_stitch_order: false,
+            order_color: None,
+            schedule: None,
+            highlighted_day: 0,
+            progress: Progress::default(),
+            stash: Stash::default(),
+            new_thread_code: String::new(),
+            new_thread_meters: floss::SKEIN_METERS,
             config: Config::default(),
-            discarded_processes: vec![]
         }
     }
 }
@@ -54,126 +258,1608 @@ impl Default for Cross {
 
 impl Cross {
     fn run_analysis(&mut self) {
-        let copied_image = self.image.clone();
-
-        // Stop current thread
-        if let Some(sender) = &self.cancel_sender {
-            _ = sender.send(true);
-        }
-
-        // Save it for future cleanup
-        if self.process_handle.is_some() {
-            self.discarded_processes.push(self.process_handle.take().expect("should exist, get rid of this silliness"));
-        }
-
-        // Cleanup any dead threads. Need to use a special-rust method, retain for this.
-        // But because .join mutates, we must use .drain_filter, which is 'unstable' and not easily usable.
-        // Do the silly approach instead
-        //for finished_handle in self.discarded_processes.drain_filter(|handle| { handle.is_finished() }) {
-        //    finished_handle.join();
-        //}
-        // https://doc.rust-lang.org/std/vec/struct.Vec.html#method.drain_filter
-        let mut i = 0;
-        while i < self.discarded_processes.len() {
-            if self.discarded_processes[i].is_finished() {
-                let finished_handle = self.discarded_processes.remove(i);
-                _ = finished_handle.join();
-            } else {
-                i += 1;
-            }
+        // Without an image there's nothing to analyze, and an opened project's chart should stay put.
+        if self.image.size[0] != 0 {
+            let stash = self.stash_limit(&self.config);
+            self.worker.submit(self.image.clone(), self.image_id, self.config.clone(), stash, self.active_mask());
         }
+    }
+
+    fn suggest_colors(&mut self) {
+        let stash = self.stash_limit(&self.config);
+        self.worker.submit_color_curve(self.image.clone(), self.image_id, self.config.clone(), stash, self.active_mask());
+    }
 
-        self.has_finished = false;
+    /// The flosses to limit the palette to, if limiting to the stash. The stash holds floss, so it only limits cross stitch.
+    fn stash_limit(&self, config: &Config) -> Option<Vec<&'static Floss>> {
+        (config.use_stash && config.craft == Craft::CrossStitch).then(|| self.stash.flosses())
+    }
 
-        let (tx, rx): (mpsc::Sender<bool>, mpsc::Receiver<bool>) = mpsc::channel();
-        self.cancel_sender = Some(tx);
+    fn active_mask(&self) -> Option<Mask> {
+        (!self.mask.is_empty()).then(|| self.mask.clone())
+    }
 
-        let copied_config = self.config.clone();
-        self.process_handle = Some(thread::spawn(|| { analysis::update_pattern(copied_image, copied_config, rx) }));
+    fn update_mask_texture(&mut self, ctx: &egui::Context) {
+        self.mask_texture = Some(ctx.load_texture("image-mask", self.mask.overlay(), egui::TextureOptions::NEAREST));
     }
 
-    fn load_image(&mut self, path: std::path::PathBuf, ui: &mut Ui) {
-        let loaded_image = input::load_image_from_path(&path);
+    fn load_image(&mut self, path: &Path, ctx: &egui::Context) -> bool {
+        let loaded_image = input::load_image_from_path(path);
         match loaded_image {
             Ok(image) =>
             {
                 // Copy the image for future use and the texture
-                self.image = image.clone();
-                self.texture = Some(ui.ctx().load_texture(
+                self.image = Arc::new(image.clone());
+                self.image_id += 1;
+                self.image_path = Some(path.to_path_buf());
+                self.color_curve = None;
+                self.mask = Mask::for_image(self.image.size);
+                self.pixel_grid = pixel_art_grid(&self.image);
+                self.update_mask_texture(ctx);
+                self.texture = Some(ctx.load_texture(
                     "loaded-image",
                     image,
                     Default::default()));
-                self.run_analysis();
+                true
             },
             Err(err) => {
-                print!("Unable to load image: {}", err)
+                print!("Unable to load image: {}", err);
+                false
+            }
+        }
+    }
+
+    fn save_project(&self, path: &Path) {
+        if let Some(chart_data) = &self.analyzed_chart {
+            let project = Project::new(self.image_path.clone(), &self.config, chart_data, &self.progress, &self.mask, &self.decorations, &self.colorways);
+            if let Err(err) = project.save(path) {
+                print!("Unable to save project: {}", err)
+            }
+        }
+    }
+
+    fn open_project(&mut self, path: &Path, ctx: &egui::Context) {
+        let project = match Project::open(path) {
+            Ok(project) => project,
+            Err(err) => {
+                print!("Unable to open project: {}", err);
+                return;
             }
         };
+
+        // The saved chart is shown as-is. Any analysis still running is for the old image.
+        self.worker.cancel();
+        self.config = project.config.clone();
+        self.config.sync_columns();
+
+        self.image = Arc::default();
+        self.image_path = None;
+        self.texture = None;
+        self.pixel_grid = None;
+        self.color_curve = None;
+        self.mask = Mask::default();
+        self.mask_texture = None;
+        if let Some(image_path) = &project.image_path {
+            self.load_image(image_path, ctx);
+        }
+
+        // Projects from before masks existed keep the blank mask made for the image.
+        if project.mask != Mask::default() {
+            self.mask = project.mask.clone();
+            self.update_mask_texture(ctx);
+        }
+
+        self.progress = project.progress.clone();
+        self.decorations = project.decorations.clone();
+        if let Some(border) = &self.decorations.border {
+            self.border = border.clone();
+        }
+        if let Some(repeat) = &self.decorations.repeat {
+            self.symmetry = repeat.symmetry;
+        }
+        self.colorways = project.colorways.clone();
+        self.shown_colorway = None;
+        self.metrics = None;
+        self.pruned_colors.clear();
+        self.set_chart_data(Some(project.chart_data()));
     }
-}
 
-impl eframe::App for Cross {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.horizontal_centered(|ui| {    
-                ui.vertical(|ui| { 
-                    // Image loading and rendering    
-                    if ui.button("Select Image file...").clicked() {
+    fn set_chart_data(&mut self, chart_data: Option<ChartData>) {
+        self.analyzed_chart = chart_data;
+        self.apply_decorations();
+    }
+
+    /// Shows the analyzed chart with the current decorations stitched over it, in the colorway being shown.
+    fn apply_decorations(&mut self) {
+        let mut chart_data = self.analyzed_chart.as_ref().map(|chart_data| self.decorations.apply(chart_data));
+        if let (Some(chart), Some(colorway)) = (&mut chart_data, self.shown_colorway.and_then(|i| self.colorways.get(i))) {
+            *chart = colorway.apply(chart, self.stash_limit(&self.config).as_deref());
+        }
+
+        // Refit the view only when the chart dimensions or cell shape change, so tweaking colors keeps the zoom.
+        let resized = match (&self.chart_data, &chart_data) {
+            (Some(old), Some(new)) => old.width != new.width || old.height != new.height || old.geometry != new.geometry,
+            _ => true,
+        };
+        if resized {
+            self.chart.reset_view();
+        }
+
+        self.chart.invalidate();
+        self.chart_data = chart_data;
+        self.stitch_plan = None;
+        self.stitch_order = None;
+        self.order_color = None;
+
+        self.schedule = self.chart_data.as_ref().map(|chart_data| schedule::plan(chart_data, self.config.num_days));
+        self.highlighted_day = 0;
+        if let Some(chart_data) = &self.chart_data {
+            self.progress.fit(chart_data);
+        }
+        self.update_overlays();
+    }
+
+    fn update_overlays(&mut self) {
+        let progress_overlay = match &self.chart_data {
+            Some(chart_data) if self.progress.done_count() > 0 => Some(self.progress.overlay(chart_data)),
+            _ => None,
+        };
+        self.chart.set_overlay("progress", progress_overlay);
+
+        let day_overlay = match (&self.chart_data, &self.schedule) {
+            (Some(chart_data), Some(schedule)) if self.highlighted_day > 0 =>
+                Some(schedule.day_overlay(chart_data, self.highlighted_day - 1)),
+            _ => None,
+        };
+        self.chart.set_overlay("schedule", day_overlay);
+
+        // Metrics and lettering cover the analyzed chart, and are moved in from any border.
+        let error_overlay = match (&self.analyzed_chart, &self.metrics) {
+            (Some(analyzed_chart), Some(metrics)) if self.showing_error_heatmap =>
+                Some(self.decorations.pad_overlay(metrics.heatmap(analyzed_chart))),
+            _ => None,
+        };
+        self.chart.set_overlay("error", error_overlay);
+
+        let text_preview = match (&self.analyzed_chart, &self.chart_data, self.preview_stitch) {
+            (Some(analyzed_chart), Some(chart_data), Some(stitch)) if self.chart_tool == ChartTool::PlaceText => {
+                let bitmap = self.lettering.render(chart_data.geometry.aspect);
+                let floss = chart_data.catalog.find(self.lettering.floss_code.trim());
+                bitmap.zip(floss).map(|(bitmap, floss)| {
+                    let stitch = self.decorations.analyzed_stitch(chart_data, stitch);
+                    let (x, y) = decorations::centered_origin(analyzed_chart, &bitmap, stitch);
+                    self.decorations.pad_overlay(decorations::preview(analyzed_chart, &bitmap, x, y, floss.color()))
+                })
+            },
+            _ => None,
+        };
+        self.chart.set_overlay("lettering", text_preview);
+
+        let tile_overlay = match (&self.analyzed_chart, &self.chart_data) {
+            (Some(analyzed_chart), Some(chart_data)) => {
+                let tile = match self.chart_tool {
+                    ChartTool::MarkTile => self.preview_stitch.map(|stitch| {
+                        let stitch = self.decorations.analyzed_stitch(chart_data, stitch);
+                        Tile::between(analyzed_chart, self.tile_corner.unwrap_or(stitch), stitch)
+                    }),
+                    ChartTool::PaintStitches => self.decorations.repeat.map(|repeat| repeat.tile),
+                    _ => None,
+                };
+                tile.map(|tile| self.decorations.pad_overlay(tile.overlay(analyzed_chart)))
+            },
+            _ => None,
+        };
+        self.chart.set_overlay("tile", tile_overlay);
+
+        let (order_overlay, order_labels) = match (&self.chart_data, &self.stitch_order) {
+            (Some(chart_data), Some(stitch_order)) if self.showing_stitch_order =>
+                (Some(stitch_order.overlay(chart_data, self.order_color)), stitch_order.labels(self.order_color)),
+            _ => (None, vec![]),
+        };
+        self.chart.set_overlay("order", order_overlay);
+        self.chart.set_labels(order_labels);
+
+        let backstitch_preview = match (&self.chart_data, self.backstitch_start, self.preview_corner) {
+            (Some(chart_data), Some(start), Some(corner)) if self.chart_tool == ChartTool::Backstitch => {
+                let inset = self.decorations.inset();
+                chart_data.catalog.find(self.backstitch_floss_code.trim()).map(|floss| (start.map(|i| i + inset), corner, floss.color()))
+            },
+            _ => None,
+        };
+        self.chart.set_preview_line(backstitch_preview);
+    }
+
+    fn click_chart(&mut self, stitch: usize, shift: bool) {
+        let (Some(analyzed_chart), Some(chart_data)) = (&self.analyzed_chart, &self.chart_data) else {
+            return;
+        };
+
+        let analyzed_stitch = self.decorations.analyzed_stitch(chart_data, stitch);
+        match self.chart_tool {
+            ChartTool::Inspect => {},
+            ChartTool::MarkProgress => {
+                self.progress.toggle(chart_data, stitch, shift);
+                self.update_overlays();
+            },
+            ChartTool::PlaceText => self.place_text(stitch),
+            ChartTool::MarkTile => match self.tile_corner.take() {
+                None => self.tile_corner = Some(analyzed_stitch),
+                Some(corner) => {
+                    let tile = Tile::between(analyzed_chart, corner, analyzed_stitch);
+                    self.decorations.repeat = Some(Repeat { tile, symmetry: self.symmetry });
+                    self.chart_tool = ChartTool::Inspect;
+                    self.apply_decorations();
+                },
+            },
+            ChartTool::PaintStitches => {
+                let (x, y) = (analyzed_stitch % analyzed_chart.width, analyzed_stitch / analyzed_chart.width);
+                let floss = (!shift).then(|| self.paint_floss_code.trim().to_string());
+                self.decorations.edit(x, y, floss.as_deref());
+                self.apply_decorations();
+            },
+            // Backstitch goes between corners rather than on stitches.
+            ChartTool::Backstitch => {},
+        }
+    }
+
+    /// Draws backstitch from corner to corner. Clicking the last corner again finishes the line,
+    /// and shift+click takes out the lines ending at a corner.
+    fn click_corner(&mut self, corner: [usize; 2], shift: bool) {
+        let Some(corner) = self.chart_data.as_ref().and_then(|chart_data| self.decorations.analyzed_corner(chart_data, corner)) else {
+            return;
+        };
+
+        if shift {
+            self.decorations.remove_backstitches_at(corner);
+            self.backstitch_start = None;
+            self.apply_decorations();
+            return;
+        }
+
+        match self.backstitch_start {
+            Some(start) if start != corner => {
+                self.decorations.add_backstitch(start, corner, self.backstitch_floss_code.trim());
+                self.backstitch_start = Some(corner);
+                self.apply_decorations();
+            },
+            Some(_) => {
+                self.backstitch_start = None;
+                self.update_overlays();
+            },
+            None => {
+                self.backstitch_start = Some(corner);
+                self.update_overlays();
+            },
+        }
+    }
+
+    /// Places the lettering centered on a stitch of the shown chart.
+    fn place_text(&mut self, stitch: usize) {
+        let (Some(analyzed_chart), Some(chart_data)) = (&self.analyzed_chart, &self.chart_data) else {
+            return;
+        };
+
+        if let Some(bitmap) = self.lettering.render(chart_data.geometry.aspect) {
+            let stitch = self.decorations.analyzed_stitch(chart_data, stitch);
+            let (x, y) = decorations::centered_origin(analyzed_chart, &bitmap, stitch);
+            self.decorations.texts.push(PlacedText {
+                text: self.lettering.text.clone(),
+                floss: self.lettering.floss_code.trim().to_string(),
+                x,
+                y,
+                bitmap,
+            });
+        }
+
+        self.chart_tool = ChartTool::Inspect;
+        self.preview_stitch = None;
+        self.apply_decorations();
+    }
+
+    fn color_count_ui(&mut self, ui: &mut Ui) {
+        ui.collapsing("Suggest color count", |ui| {
+            ui.add(egui::Slider::new(&mut self.config.target_error, config::TARGET_ERROR_RANGE).text("Target mean ΔE"))
+                .on_hover_text("Suggests the fewest colors that get this close to the image on average");
+
+            // An imported palette fixes the colors, so there's no count to suggest.
+            let fixed_palette = !self.config.fixed_palette.is_empty();
+            let can_suggest = self.image.size[0] != 0 && !self.worker.is_busy() && !fixed_palette;
+            if ui.add_enabled(can_suggest, egui::Button::new("Try color counts"))
+                .on_disabled_hover_text(if fixed_palette { "The imported palette sets the colors" } else { "Needs an image, and no analysis running" })
+                .clicked() {
+                self.suggest_colors();
+            }
+
+            let Some(color_curve) = self.color_curve.as_ref().filter(|_| !fixed_palette) else {
+                return;
+            };
+
+            let suggestion = color_curve.suggest(self.config.target_error);
+            let points: Vec<[f64; 2]> = color_curve.points.iter().map(|(count, error)| [*count as f64, *error as f64]).collect();
+            egui::plot::Plot::new("color_curve")
+                .height(150.0)
+                .allow_drag(false)
+                .allow_zoom(false)
+                .allow_scroll(false)
+                .show(ui, |plot_ui| {
+                    plot_ui.line(egui::plot::Line::new(points).name("Mean ΔE"));
+                    plot_ui.hline(egui::plot::HLine::new(self.config.target_error).name("Target"));
+                    plot_ui.vline(egui::plot::VLine::new(suggestion.num_colors).name("Suggested"));
+                });
+
+            if !suggestion.reached_target {
+                ui.label("No color count reaches the target, suggesting where more colors stop helping much");
+            }
+            if ui.button(format!("Use {} colors", suggestion.num_colors)).clicked() {
+                self.config.num_colors = suggestion.num_colors;
+            }
+        });
+    }
+
+    fn quality_ui(&mut self, ui: &mut Ui) {
+        let Some(metrics) = &self.metrics else {
+            return;
+        };
+
+        let mut overlays_changed = false;
+        ui.collapsing("Quality", |ui| {
+            ui.label(format!("Color error (ΔE): mean {:.1}, max {:.1}", metrics.mean_error, metrics.max_error))
+                .on_hover_text("Difference between each stitch's floss and the image. Below about 2.3 is barely noticeable.");
+            ui.label(format!("{:.1} color changes per row", metrics.color_changes_per_row));
+            ui.label(format!("{} isolated stitches", metrics.isolated_stitches));
+            overlays_changed = ui.checkbox(&mut self.showing_error_heatmap, "Show error heatmap").changed();
+        });
+
+        if overlays_changed {
+            self.update_overlays();
+        }
+    }
+
+    fn schedule_ui(&mut self, ui: &mut Ui) {
+        let (Some(chart_data), Some(schedule)) = (&self.chart_data, &self.schedule) else {
+            return;
+        };
+
+        let mut overlays_changed = false;
+        ui.collapsing("Schedule", |ui| {
+            ui.label(format!("{} days at about {} stitches per day", schedule.days.len(), schedule.stitches_per_day));
+            overlays_changed = ui.add(egui::Slider::new(&mut self.highlighted_day, 0..=schedule.days.len()).text("Highlight day")).changed();
+
+            if self.highlighted_day > 0 {
+                for task in &schedule.days[self.highlighted_day - 1].tasks {
+                    ui.label(task.describe(chart_data));
+                }
+            }
+
+            if ui.button("Export checklist...").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("markdown", &["md"])
+                    .set_file_name("schedule.md")
+                    .save_file() {
+                        if let Err(err) = std::fs::write(&path, schedule.to_checklist(chart_data)) {
+                            print!("Unable to save checklist: {}", err)
+                        }
+                }
+            }
+        });
+
+        if overlays_changed {
+            self.update_overlays();
+        }
+    }
+
+    fn stitch_order_ui(&mut self, ui: &mut Ui) {
+        let Some(chart_data) = &self.chart_data else {
+            return;
+        };
+
+        let mut overlays_changed = false;
+        ui.collapsing("Stitching order", |ui| {
+            ui.horizontal(|ui| {
+                let travel_changed = ui.add(egui::DragValue::new(&mut self.config.max_travel).clamp_range(config::MAX_TRAVEL_RANGE).speed(0.1).prefix("Longest carry: ").suffix(" stitches"))
+                    .on_hover_text("Thread carried further than this across the back is ended and started again")
+                    .changed();
+                if ui.button("Plan order").clicked() || (travel_changed && self.stitch_order.is_some()) {
+                    self.stitch_order = Some(order::plan(chart_data, self.config.max_travel));
+                    overlays_changed = true;
+                }
+            });
+
+            let Some(stitch_order) = &self.stitch_order else {
+                return;
+            };
+
+            ui.horizontal(|ui| {
+                overlays_changed |= ui.checkbox(&mut self.showing_stitch_order, "Number steps on the chart")
+                    .on_hover_text("Each step starts at its number. Red stitches are where a thread is started again.")
+                    .changed();
+                let selected = self.order_color.map_or_else(|| "All colors".to_string(), |i| chart_data.palette[i].to_string());
+                egui::ComboBox::from_id_source("order_color")
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        overlays_changed |= ui.selectable_value(&mut self.order_color, None, "All colors").changed();
+                        for color_plan in &stitch_order.colors {
+                            let name = chart_data.palette[color_plan.palette_index].to_string();
+                            overlays_changed |= ui.selectable_value(&mut self.order_color, Some(color_plan.palette_index), name).changed();
+                        }
+                    });
+            });
+
+            let difficulty = &stitch_order.difficulty;
+            ui.label(format!("Difficulty: {:.1} of 10", difficulty.score))
+                .on_hover_text("Mostly how many colors share each 10x10 block, then confetti, thread starts and palette size");
+            ui.label(format!("{} colors, {} stitches, {} confetti stitches", difficulty.colors, difficulty.stitches, difficulty.confetti));
+            ui.label(format!("{:.1} colors per 10x10 block", difficulty.colors_per_block));
+            ui.label(format!("{} thread starts, {:.0} stitches of thread carried", difficulty.thread_starts, difficulty.travel));
+            if difficulty.long_travels > 0 {
+                ui.colored_label(ui.visuals().warn_fg_color, format!("{} carries longer than {} stitches", difficulty.long_travels, self.config.max_travel));
+            }
+
+            egui::ScrollArea::vertical().id_source("order_colors").max_height(200.0).show(ui, |ui| {
+                for color_plan in &stitch_order.colors {
+                    let color = &chart_data.palette[color_plan.palette_index];
+                    ui.horizontal(|ui| {
+                        renderer::color_swatch(ui, color.color);
+                        ui.label(format!("{}: {}, {} stitches in {} areas, {} thread starts",
+                            color, color_plan.method.name(), color_plan.stitches, color_plan.regions, color_plan.thread_starts));
+                    });
+                }
+            });
+        });
+
+        if overlays_changed {
+            self.update_overlays();
+        }
+    }
+
+    /// Returns true if the stash changed in a way that affects the chart.
+    fn stash_ui(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+        ui.collapsing("Thread stash", |ui| {
+            ui.add_enabled(self.config.craft == Craft::CrossStitch, egui::Checkbox::new(&mut self.config.use_stash, "Only use threads from my stash"))
+                .on_hover_text("With an empty stash, any floss may be used")
+                .on_disabled_hover_text("The stash holds floss, so it only applies to cross stitch");
+
+            ui.horizontal(|ui| {
+                if ui.button("Load stash...").clicked() {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("csv", &["csv"])
+                        .pick_file() {
+                            match Stash::load(&path) {
+                                Ok(stash) => {
+                                    self.stash = stash;
+                                    changed = true;
+                                },
+                                Err(err) => print!("Unable to load stash: {}", err),
+                            }
+                    }
+                }
+
+                if ui.button("Save stash...").clicked() {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("csv", &["csv"])
+                        .set_file_name("stash.csv")
+                        .save_file() {
+                            if let Err(err) = self.stash.save(&path) {
+                                print!("Unable to save stash: {}", err)
+                            }
+                    }
+                }
+            });
+
+            let mut removed = None;
+            egui::ScrollArea::vertical().id_source("stash_threads").max_height(200.0).show(ui, |ui| {
+                for (i, thread) in self.stash.threads.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        renderer::color_swatch(ui, thread.floss.color());
+                        ui.label(thread.floss.to_string());
+                        ui.add(egui::DragValue::new(&mut thread.meters).clamp_range(0.0..=1000.0).speed(0.1).suffix(" m"));
+                        if ui.small_button("Remove").clicked() {
+                            removed = Some(i);
+                        }
+                    });
+                }
+            });
+            if let Some(i) = removed {
+                self.stash.threads.remove(i);
+                changed = true;
+            }
+
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.new_thread_code).hint_text(Catalog::Dmc.code_name()).desired_width(60.0));
+                ui.add(egui::DragValue::new(&mut self.new_thread_meters).clamp_range(0.0..=1000.0).speed(0.1).suffix(" m"));
+                let new_floss = Catalog::Dmc.find(self.new_thread_code.trim());
+                if ui.add_enabled(new_floss.is_some(), egui::Button::new("Add")).clicked() {
+                    if let Some(new_floss) = new_floss {
+                        self.stash.add(new_floss, self.new_thread_meters);
+                        self.new_thread_code.clear();
+                        changed = true;
+                    }
+                }
+            });
+        });
+
+        // Lengths only matter for the usage warnings, not for which flosses the chart may use.
+        changed && self.config.use_stash
+    }
+
+    fn target_palette_ui(&mut self, ui: &mut Ui) {
+        ui.collapsing("Target palette", |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Import palette...").on_hover_text("GIMP, Adobe or Procreate palette, used instead of picking colors from the image").clicked() {
+                    let extensions = swatches::Format::ALL.map(swatches::Format::extension);
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("palette", &extensions)
+                        .pick_file() {
+                            match swatches::load(&path) {
+                                Ok(imported) => {
+                                    self.config.fixed_palette = imported.iter().map(|swatch| [swatch.color.r(), swatch.color.g(), swatch.color.b()]).collect();
+                                },
+                                Err(err) => print!("Unable to load palette: {}", err),
+                            }
+                    }
+                }
+
+                if ui.add_enabled(!self.config.fixed_palette.is_empty(), egui::Button::new("Clear")).clicked() {
+                    self.config.fixed_palette.clear();
+                }
+            });
+
+            if self.config.fixed_palette.is_empty() {
+                ui.label("Colors are picked from the image");
+                return;
+            }
+
+            ui.label(format!("Stitches use the closest of {} imported colors", self.config.fixed_palette.len()));
+            ui.horizontal_wrapped(|ui| {
+                for [r, g, b] in &self.config.fixed_palette {
+                    renderer::color_swatch(ui, egui::Color32::from_rgb(*r, *g, *b));
+                }
+            });
+        });
+    }
+
+    fn palette_ui(&mut self, ui: &mut Ui) {
+        let Some(chart_data) = &self.chart_data else {
+            return;
+        };
+
+        let name = self.export_name();
+        ui.collapsing("Palette", |ui| {
+            ui.horizontal(|ui| {
+                for format in swatches::Format::ALL {
+                    if ui.button(format!("Export {}...", format.name())).clicked() {
                         if let Some(path) = rfd::FileDialog::new()
-                            .add_filter("images", &["jpg", "jpeg", "png"])
-                            .pick_file() {
-                                self.load_image(path, ui);
+                            .add_filter(format.extension(), &[format.extension()])
+                            .set_file_name(&format!("{}.{}", name, format.extension()))
+                            .save_file() {
+                                if let Err(err) = swatches::save(&path, &name, &swatches::from_chart(chart_data)) {
+                                    print!("Unable to save palette: {}", err)
+                                }
                         }
                     }
+                }
+            });
+
+            let duplicates: Vec<DuplicatePair> = analysis::near_duplicates(chart_data, self.config.duplicate_delta_e);
+            if !duplicates.is_empty() {
+                ui.colored_label(ui.visuals().warn_fg_color, format!("{} pairs of colors are hard to tell apart", duplicates.len()));
+                for pair in &duplicates {
+                    let (first, second) = (&chart_data.palette[pair.first], &chart_data.palette[pair.second]);
+                    ui.horizontal(|ui| {
+                        renderer::color_swatch(ui, first.thread_color());
+                        renderer::color_swatch(ui, second.thread_color());
+                        ui.label(format!("{} and {}: ΔE {:.1}", first, second, pair.delta_e));
+                    });
+                }
+                if ui.button("Merge them").on_hover_text("Turns on merging near-duplicates, and re-analyzes").clicked() {
+                    self.config.merge_duplicates = true;
+                }
+            }
 
-                    if self.image.size[0] != 0 {
-                        ui.label(format!("Image size: [{},{}]", self.image.size[0], self.image.size[1]));
+            if !self.pruned_colors.is_empty() {
+                let pruned_stitches: usize = self.pruned_colors.iter().map(|pruned| pruned.stitches).sum();
+                ui.collapsing(format!("Pruned {} colors ({} stitches)", self.pruned_colors.len(), pruned_stitches), |ui| {
+                    for pruned in &self.pruned_colors {
+                        ui.horizontal(|ui| {
+                            renderer::color_swatch(ui, pruned.color.color);
+                            ui.label(format!("{}: {} stitches, now", pruned.color, pruned.stitches));
+                            renderer::color_swatch(ui, pruned.merged_into.color);
+                            ui.label(pruned.merged_into.to_string());
+                        });
                     }
+                });
+            }
+
+            let without_symbol = chart_data.colors_without_symbol();
+            if without_symbol > 0 {
+                ui.colored_label(ui.visuals().warn_fg_color, format!("{} colors have no chart symbol left; use fewer colors", without_symbol));
+            }
 
-                    if let Some(texture) = &self.texture {
-                        // Scale image down to 200x(aspect-ratio)
-                        let width = 200.0;
-                        let height = width *
-                            (self.image.size[1] as f32 / self.image.size[0] as f32);
-                        ui.image(texture, egui::Vec2::new(width, height));
+            let symbols = chart_data.symbols();
+            egui::ScrollArea::vertical().id_source("palette_colors").max_height(200.0).show(ui, |ui| {
+                for ((color, symbol), count) in chart_data.palette.iter().zip(symbols).zip(chart_data.color_counts()) {
+                    if count == 0 {
+                        continue;
                     }
 
-                    // Generation controls
-                    ui.add(egui::Slider::new(&mut self.config.num_width, 10..=200).text("Width"));
-                    ui.add(egui::Slider::new(&mut self.config.num_height, 10..=200).text("Height"));
-                    ui.add(egui::Slider::new(&mut self.config.num_days, 1..=365).text("Days"));
-                    ui.label("Colorization settings");
-                    ui.add(egui::Slider::new(&mut self.config.num_colors, 2..=50).text("Colors"));
-                    ui.add(egui::Slider::new(&mut self.config.num_iterations, 1..=100).text("kNN Iterations"));
+                    ui.horizontal(|ui| {
+                        renderer::color_swatch(ui, color.color);
+                        ui.label(format!("{} {}: {} stitches", symbol, color, count));
+                    });
+                }
+            });
 
-                    if self.config.recalculate_columns() {
-                        self.run_analysis();
+            // Blends share flosses with other colors, so usage is totalled per floss.
+            // The stash holds floss, so it's only compared against for cross stitch.
+            let usage = chart_data.floss_usage();
+            let craft = self.config.craft;
+            if craft != Craft::CrossStitch {
+                ui.label("Materials needed");
+                egui::ScrollArea::vertical().id_source("palette_materials").max_height(200.0).show(ui, |ui| {
+                    for (floss, cells) in usage {
+                        ui.horizontal(|ui| {
+                            renderer::color_swatch(ui, floss.color());
+                            ui.label(format!("{}: {}", floss, craft.describe_usage(cells, &self.config)));
+                        });
                     }
+                });
+                return;
+            }
+
+            let short_flosses = usage.iter().filter(|(floss, stitches)| {
+                matches!(self.stash.meters_of(floss), Some(meters) if meters < floss::meters_needed(*stitches))
+            }).count();
+            ui.label("Floss needed");
+            if short_flosses > 0 {
+                ui.colored_label(ui.visuals().warn_fg_color, format!("{} flosses need more than the stash has", short_flosses));
+            }
+
+            egui::ScrollArea::vertical().id_source("palette_flosses").max_height(200.0).show(ui, |ui| {
+                for (floss, stitches) in usage {
+                    let needed = floss::meters_needed(stitches);
+                    ui.horizontal(|ui| {
+                        renderer::color_swatch(ui, floss.color());
+                        ui.label(format!("{}: {}", floss, craft.describe_usage(stitches, &self.config)));
+                        match self.stash.meters_of(floss) {
+                            Some(meters) if meters < needed =>
+                                ui.colored_label(ui.visuals().warn_fg_color, format!("only {:.1} m in stash", meters)),
+                            Some(meters) => ui.label(format!("{:.1} m in stash", meters)),
+                            None => ui.label("not in stash"),
+                        };
+                    });
+                }
+            });
+        });
+    }
+
+    fn progress_ui(&mut self, ui: &mut Ui) {
+        let Some(chart_data) = &self.chart_data else {
+            return;
+        };
 
-                    // TODO add aspect ratio lock
-                    // TODO add config for the output generation
+        ui.collapsing("Progress", |ui| {
+            let mut tracking = self.chart_tool == ChartTool::MarkProgress;
+            if ui.checkbox(&mut tracking, "Mark stitches on chart")
+                .on_hover_text("Click a stitch to mark it done, or shift+click to mark its whole color block")
+                .changed() {
+                    self.chart_tool = if tracking { ChartTool::MarkProgress } else { ChartTool::Inspect };
+            }
+
+            let total = chart_data.stitches.len();
+            let done = self.progress.done_count();
+            let remaining = (total - done) as f64;
+            ui.label(format!("{} of {} stitches done ({:.1}%)", done, total, percent(done, total)));
+            ui.label(format!("About {} days left at {} stitches per day", (remaining / PIXELS_PER_DAY_AVG).ceil(), PIXELS_PER_DAY_AVG));
+            if let Some(pace) = self.progress.pace() {
+                if pace > 0.0 {
+                    ui.label(format!("About {} days left at your pace of {:.0} stitches per day", (remaining / pace).ceil(), pace));
+                }
+            }
+
+            egui::ScrollArea::vertical().id_source("progress_colors").max_height(200.0).show(ui, |ui| {
+                for (color, (color_done, color_total)) in chart_data.palette.iter().zip(self.progress.color_counts(chart_data)) {
+                    if color_total == 0 {
+                        continue;
+                    }
+
+                    ui.horizontal(|ui| {
+                        renderer::color_swatch(ui, color.color);
+                        ui.label(format!("{}: {}/{} ({:.0}%)", color, color_done, color_total, percent(color_done, color_total)));
+                    });
+                }
+            });
+        });
+    }
+
+    fn repeat_ui(&mut self, ui: &mut Ui) {
+        if self.chart_data.is_none() {
+            return;
+        }
+
+        let mut changed = false;
+        let mut overlays_changed = false;
+        ui.collapsing("Repeats and stitch edits", |ui| {
+            let symmetry = self.symmetry;
+            egui::ComboBox::from_label("Symmetry")
+                .selected_text(self.symmetry.name())
+                .show_ui(ui, |ui| {
+                    for symmetry in Symmetry::ALL {
+                        ui.selectable_value(&mut self.symmetry, symmetry, symmetry.name());
+                    }
+                })
+                .response
+                .on_hover_text("Mirrors are about the middle of the chart. 8-way mirroring needs a square chart.");
+            if symmetry != self.symmetry {
+                if let Some(repeat) = &mut self.decorations.repeat {
+                    repeat.symmetry = self.symmetry;
+                    changed = true;
+                }
+            }
+
+            ui.horizontal(|ui| {
+                if self.chart_tool == ChartTool::MarkTile {
+                    ui.label(if self.tile_corner.is_some() { "Click the opposite corner" } else { "Click a corner of the tile" });
+                    if ui.button("Cancel").clicked() {
+                        self.chart_tool = ChartTool::Inspect;
+                        self.tile_corner = None;
+                        overlays_changed = true;
+                    }
+                } else if ui.button("Mark tile").clicked() {
+                    self.chart_tool = ChartTool::MarkTile;
+                    self.tile_corner = None;
+                }
+
+                if ui.add_enabled(self.decorations.repeat.is_some(), egui::Button::new("Remove repeat")).clicked() {
+                    self.decorations.repeat = None;
+                    changed = true;
+                }
+            });
+            if let Some(Repeat { tile, .. }) = &self.decorations.repeat {
+                ui.label(format!("Tile: {} x {} stitches at ({}, {})", tile.width, tile.height, tile.x + 1, tile.y + 1));
+            }
+
+            ui.separator();
+            let catalog = self.config.craft.catalog();
+            let floss = catalog.find(self.paint_floss_code.trim());
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.paint_floss_code).hint_text(catalog.code_name()).desired_width(60.0));
+                match floss {
+                    Some(floss) => {
+                        renderer::color_swatch(ui, floss.color());
+                        ui.label(floss.to_string());
+                    },
+                    None => {
+                        ui.colored_label(ui.visuals().warn_fg_color, "Unknown floss");
+                    },
+                }
+            });
+
+            let mut painting = self.chart_tool == ChartTool::PaintStitches;
+            if ui.add_enabled(floss.is_some(), egui::Checkbox::new(&mut painting, "Paint stitches on chart"))
+                .on_hover_text("Click a stitch to paint it, or shift+click to put it back as analyzed. Edits inside the tile are repeated.")
+                .changed() {
+                    self.chart_tool = if painting { ChartTool::PaintStitches } else { ChartTool::Inspect };
+                    overlays_changed = true;
+            }
+            if self.chart_tool == ChartTool::PaintStitches && floss.is_none() {
+                self.chart_tool = ChartTool::Inspect;
+                overlays_changed = true;
+            }
+
+            ui.horizontal(|ui| {
+                ui.label(format!("{} stitches edited", self.decorations.edits.len()));
+                if ui.add_enabled(!self.decorations.edits.is_empty(), egui::Button::new("Clear edits")).clicked() {
+                    self.decorations.edits.clear();
+                    changed = true;
+                }
+            });
+        });
+
+        if changed {
+            self.apply_decorations();
+        } else if overlays_changed {
+            self.update_overlays();
+        }
+    }
+
+    fn backstitch_ui(&mut self, ui: &mut Ui) {
+        let Some(chart_data) = &self.chart_data else {
+            return;
+        };
+
+        let square_grid = chart_data.geometry.offset == Offset::None;
+        let mut changed = false;
+        let mut overlays_changed = false;
+        ui.collapsing("Backstitch", |ui| {
+            let catalog = chart_data.catalog;
+            let floss = catalog.find(self.backstitch_floss_code.trim());
+            ui.horizontal(|ui| {
+                overlays_changed |= ui.add(egui::TextEdit::singleline(&mut self.backstitch_floss_code).hint_text(catalog.code_name()).desired_width(60.0)).changed();
+                match floss {
+                    Some(floss) => {
+                        renderer::color_swatch(ui, floss.color());
+                        ui.label(floss.to_string());
+                    },
+                    None => {
+                        ui.colored_label(ui.visuals().warn_fg_color, "Unknown floss");
+                    },
+                }
+            });
+
+            let mut drawing = self.chart_tool == ChartTool::Backstitch;
+            if ui.add_enabled(floss.is_some() && square_grid, egui::Checkbox::new(&mut drawing, "Draw backstitch on chart"))
+                .on_hover_text("Click grid corners to stitch a line between them, and the last corner again to finish. Shift+click a corner to take out the lines ending there.")
+                .changed() {
+                    self.chart_tool = if drawing { ChartTool::Backstitch } else { ChartTool::Inspect };
+                    self.backstitch_start = None;
+                    overlays_changed = true;
+            }
+            if self.chart_tool == ChartTool::Backstitch && (floss.is_none() || !square_grid) {
+                self.chart_tool = ChartTool::Inspect;
+                self.backstitch_start = None;
+                overlays_changed = true;
+            }
+            if !square_grid {
+                ui.label("Backstitch needs a square grid to follow.");
+            }
+
+            ui.horizontal(|ui| {
+                ui.label(format!("{} backstitch lines", self.decorations.backstitches.len()));
+                if ui.add_enabled(!self.decorations.backstitches.is_empty(), egui::Button::new("Clear backstitch")).clicked() {
+                    self.decorations.backstitches.clear();
+                    self.backstitch_start = None;
+                    changed = true;
+                }
+            });
+        });
+
+        if changed {
+            self.apply_decorations();
+        } else if overlays_changed {
+            self.update_overlays();
+        }
+    }
+
+    fn border_ui(&mut self, ui: &mut Ui) {
+        if self.chart_data.is_none() {
+            return;
+        }
+
+        let mut changed = false;
+        ui.collapsing("Border", |ui| {
+            let mut enabled = self.decorations.border.is_some();
+            changed |= ui.checkbox(&mut enabled, "Add a border")
+                .on_hover_text("Extends the chart on every side. Changing its size starts progress over.")
+                .changed();
+
+            egui::ComboBox::from_label("Motif")
+                .selected_text(self.border.motif.name())
+                .show_ui(ui, |ui| {
+                    for motif in Motif::ALL {
+                        changed |= ui.selectable_value(&mut self.border.motif, motif, motif.name()).changed();
+                    }
+                });
+            changed |= ui.add(egui::Slider::new(&mut self.border.margin, border::MARGIN_RANGE).text("Margin")).changed();
+
+            let catalog = self.config.craft.catalog();
+            for (label, code) in [("Motif floss", &mut self.border.floss), ("Background floss", &mut self.border.background)] {
+                ui.horizontal(|ui| {
+                    changed |= ui.add(egui::TextEdit::singleline(code).hint_text(catalog.code_name()).desired_width(60.0)).changed();
+                    match catalog.find(code.trim()) {
+                        Some(floss) => {
+                            renderer::color_swatch(ui, floss.color());
+                            ui.label(format!("{}: {}", label, floss));
+                        },
+                        None => {
+                            ui.colored_label(ui.visuals().warn_fg_color, format!("{}: unknown floss", label));
+                        },
+                    }
+                });
+            }
+
+            // Half-typed floss codes leave the last good border in place.
+            if changed {
+                self.border.floss = self.border.floss.trim().to_string();
+                self.border.background = self.border.background.trim().to_string();
+                let border = enabled.then(|| self.border.clone());
+                changed = match border {
+                    Some(border) if !border.is_valid(catalog) => false,
+                    border => {
+                        self.decorations.border = border;
+                        true
+                    },
+                };
+            }
+        });
+
+        if changed {
+            self.apply_decorations();
+        }
+    }
+
+    fn colorway_ui(&mut self, ui: &mut Ui) {
+        if self.analyzed_chart.is_none() {
+            return;
+        }
+
+        let mut changed = false;
+        let name = self.export_name();
+        let stash = self.stash_limit(&self.config);
+        ui.collapsing("Colorways", |ui| {
+            changed |= ui.radio_value(&mut self.shown_colorway, None, "Original colors").changed();
+
+            let mut removed = None;
+            for (i, colorway) in self.colorways.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    changed |= ui.radio_value(&mut self.shown_colorway, Some(i), colorway.name.as_str()).changed();
+                    ui.label(colorway.recolor.name());
+                    if let Recolor::HueRotation { degrees } = &mut colorway.recolor {
+                        changed |= ui.add(egui::DragValue::new(degrees).clamp_range(-180.0..=180.0).suffix("°")).changed();
+                    }
+
+                    if ui.button("Export...").on_hover_text("Chart in this colorway, as SVG, PNG or JSON data").clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("svg", &["svg"])
+                            .add_filter("png", &["png"])
+                            .add_filter("json", &["json"])
+                            .set_file_name(&format!("{}-{}.svg", name, colorway.file_name()))
+                            .save_file() {
+                                let chart_data = self.decorations.apply(self.analyzed_chart.as_ref().expect("checked above"));
+                                if let Err(err) = colorway.save(&path, &chart_data, &self.config, stash.as_deref()) {
+                                    print!("Unable to save colorway: {}", err)
+                                }
+                        }
+                    }
+
+                    if ui.small_button("Remove").clicked() {
+                        removed = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = removed {
+                self.colorways.remove(i);
+                self.shown_colorway = None;
+                changed = true;
+            }
+
+            // Substitutions for the colorway being shown, listing each pattern color and what it becomes
+            if let (Some(i), Some(analyzed_chart)) = (self.shown_colorway, &self.analyzed_chart) {
+                let chart_data = self.decorations.apply(analyzed_chart);
+                let colorway = &mut self.colorways[i];
+                ui.label(format!("Substitutions, by {}", chart_data.catalog.code_name()));
+                egui::ScrollArea::vertical().id_source("colorway_substitutions").max_height(200.0).show(ui, |ui| {
+                    for (color, count) in chart_data.palette.iter().zip(chart_data.color_counts()) {
+                        if count == 0 {
+                            continue;
+                        }
+
+                        let key = colorway::thread_key(color);
+                        let mut code = colorway.substitution(&key).unwrap_or_default().to_string();
+                        ui.horizontal(|ui| {
+                            renderer::color_swatch(ui, color.color);
+                            ui.label("→");
+                            renderer::color_swatch(ui, colorway.palette_color(color, chart_data.catalog, stash.as_deref()).color);
+                            if ui.add(egui::TextEdit::singleline(&mut code).hint_text(chart_data.catalog.code_name()).desired_width(60.0)).changed() {
+                                colorway.substitute(&key, code.trim());
+                                changed = true;
+                            }
+                            if !code.trim().is_empty() && chart_data.catalog.find(code.trim()).is_none() {
+                                ui.colored_label(ui.visuals().warn_fg_color, "unknown floss");
+                            } else {
+                                ui.label(colorway.palette_color(color, chart_data.catalog, stash.as_deref()).to_string());
+                            }
+                        });
+                    }
+                });
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.new_colorway_name).hint_text("Name").desired_width(100.0));
+                egui::ComboBox::from_id_source("new_recolor")
+                    .selected_text(self.new_recolor.name())
+                    .show_ui(ui, |ui| {
+                        for recolor in [Recolor::HueRotation { degrees: 180.0 }, Recolor::TargetPalette { colors: vec![] }, Recolor::Unchanged] {
+                            let selected = std::mem::discriminant(&recolor) == std::mem::discriminant(&self.new_recolor);
+                            if ui.selectable_label(selected, recolor.name()).clicked() && !selected {
+                                self.new_recolor = recolor;
+                            }
+                        }
+                    });
+            });
+
+            match &mut self.new_recolor {
+                Recolor::HueRotation { degrees } => {
+                    ui.add(egui::Slider::new(degrees, -180.0..=180.0).text("Hue rotation").suffix("°"));
+                },
+                Recolor::TargetPalette { colors } => {
+                    ui.horizontal(|ui| {
+                        if ui.button("Import target palette...").clicked() {
+                            let extensions = swatches::Format::ALL.map(swatches::Format::extension);
+                            if let Some(path) = rfd::FileDialog::new()
+                                .add_filter("palette", &extensions)
+                                .pick_file() {
+                                    match swatches::load(&path) {
+                                        Ok(imported) => *colors = imported.iter().map(|swatch| [swatch.color.r(), swatch.color.g(), swatch.color.b()]).collect(),
+                                        Err(err) => print!("Unable to load palette: {}", err),
+                                    }
+                            }
+                        }
+                        ui.label(format!("{} colors", colors.len()));
+                    });
+                },
+                Recolor::Unchanged => {},
+            }
+
+            let ready = !self.new_colorway_name.trim().is_empty() && self.new_recolor != Recolor::TargetPalette { colors: vec![] };
+            if ui.add_enabled(ready, egui::Button::new("Add colorway")).clicked() {
+                self.colorways.push(Colorway::new(self.new_colorway_name.trim().to_string(), self.new_recolor.clone()));
+                self.shown_colorway = Some(self.colorways.len() - 1);
+                self.new_colorway_name.clear();
+                changed = true;
+            }
+
+            if ui.add_enabled(!self.colorways.is_empty(), egui::Button::new("Export all as SVG...")).clicked() {
+                if let Some(folder) = rfd::FileDialog::new().pick_folder() {
+                    let chart_data = self.decorations.apply(self.analyzed_chart.as_ref().expect("checked above"));
+                    for colorway in &self.colorways {
+                        let path = folder.join(format!("{}-{}.svg", name, colorway.file_name()));
+                        if let Err(err) = std::fs::write(&path, svg::to_svg(&colorway.apply(&chart_data, stash.as_deref()))) {
+                            print!("Unable to save colorway {}: {}", colorway.name, err)
+                        }
+                    }
+                }
+            }
+        });
+
+        if changed {
+            self.apply_decorations();
+        }
+    }
+
+    fn start_batch(&mut self) {
+        let Some(output_dir) = self.batch_output.clone() else {
+            return;
+        };
+
+        let images = match batch::find_images(self.batch_input.trim()) {
+            Ok(images) => images,
+            Err(err) => {
+                self.batch_message = format!("Unable to find images: {}", err);
+                return;
+            }
+        };
+        if images.is_empty() {
+            self.batch_message = "No images found".to_string();
+            return;
+        }
+
+        let config = self.batch_preset.as_ref().map_or_else(|| self.config.clone(), |(_, config)| config.clone());
+        let stash = self.stash_limit(&config);
+        self.batch_message.clear();
+        self.batch_results.clear();
+        self.batch = Some(Batch::start(images, config, stash, output_dir, self.batch_exports.clone()));
+    }
+
+    /// Base name for exported files, from the source image
+    fn export_name(&self) -> String {
+        self.image_path.as_ref()
+            .and_then(|path| path.file_stem())
+            .map_or_else(|| "pattern".to_string(), |stem| stem.to_string_lossy().to_string())
+    }
+
+    fn chart_export_ui(&mut self, ui: &mut Ui) {
+        let Some(chart_data) = &self.chart_data else {
+            return;
+        };
+
+        let name = self.export_name();
+        ui.collapsing("Chart export", |ui| {
+            if ui.button("Export SVG...").on_hover_text("Colors, symbols, grid, rulers and legend as separate Inkscape layers").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("svg", &["svg"])
+                    .set_file_name(&format!("{}.svg", name))
+                    .save_file() {
+                        if let Err(err) = std::fs::write(&path, svg::to_svg(chart_data)) {
+                            print!("Unable to save SVG chart: {}", err)
+                        }
+                }
+            }
+
+            if ui.button("Export data...").on_hover_text("Palette, stitches and settings as JSON, with stitch and floss lists as CSV beside it").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("json", &["json"])
+                    .set_file_name(&format!("{}-data.json", name))
+                    .save_file() {
+                        if let Err(err) = data::save(&path, chart_data, &self.config) {
+                            print!("Unable to save pattern data: {}", err)
+                        }
+                }
+            }
+        });
+    }
+
+    fn embroidery_ui(&mut self, ui: &mut Ui) {
+        let Some(chart_data) = &self.chart_data else {
+            return;
+        };
+
+        let name = self.export_name();
+        ui.collapsing("Machine embroidery", |ui| {
+            let cell_mm = self.config.craft.cell_mm(&self.config);
+            ui.horizontal(|ui| {
+                if ui.button("Plan stitches").clicked() {
+                    let plan = StitchPlan::new(chart_data, cell_mm);
+                    let texture = ui.ctx().load_texture("stitch-path", plan.preview(), egui::TextureOptions::LINEAR);
+                    self.stitch_plan = Some((plan, texture));
+                }
+
+                for (label, extension) in [("Export DST...", "dst"), ("Export PES...", "pes")] {
+                    if ui.button(label).clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter(extension, &[extension])
+                            .set_file_name(&format!("{}.{}", name, extension))
+                            .save_file() {
+                                let plan = StitchPlan::new(chart_data, cell_mm);
+                                let bytes = if extension == "dst" { plan.to_dst(&name) } else { plan.to_pes(&name) };
+                                if let Err(err) = std::fs::write(&path, bytes) {
+                                    print!("Unable to save embroidery file: {}", err)
+                                }
+                        }
+                    }
+                }
+            });
+
+            if let Some((plan, texture)) = &self.stitch_plan {
+                ui.label(format!("{} stitches in {} colors", plan.stitch_count(), plan.colors.len()));
+                ui.label(format!("{} jumps, {:.0} mm of travel", plan.jump_count(), plan.jump_length()))
+                    .on_hover_text("Jumps are drawn in red. Each one leaves a thread to trim.");
+                let width = ui.available_width().min(400.0);
+                let size = texture.size_vec2();
+                ui.image(texture, egui::Vec2::new(width, width * size.y / size.x));
+            }
+        });
+    }
+
+    fn batch_ui(&mut self, ui: &mut Ui) {
+        ui.collapsing("Batch convert", |ui| {
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.batch_input).hint_text("Folder, or pattern like photos/*.jpg"));
+                if ui.button("Pick folder...").clicked() {
+                    if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                        self.batch_input = path.display().to_string();
+                    }
+                }
+            });
+
+            ui.horizontal(|ui| {
+                let preset_name = self.batch_preset.as_ref().map_or("current settings", |(name, _)| name);
+                ui.label(format!("Settings: {}", preset_name));
+                if ui.button("Load preset...").clicked() {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("preset", &["json"])
+                        .pick_file() {
+                            match Config::load(&path) {
+                                Ok(config) => {
+                                    let name = path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().to_string());
+                                    self.batch_preset = Some((name, config));
+                                },
+                                Err(err) => print!("Unable to load preset: {}", err),
+                            }
+                    }
+                }
+                if ui.add_enabled(self.batch_preset.is_some(), egui::Button::new("Use current")).clicked() {
+                    self.batch_preset = None;
+                }
+            });
+            if ui.button("Save current settings as preset...").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("preset", &["json"])
+                    .set_file_name("preset.json")
+                    .save_file() {
+                        if let Err(err) = self.config.save(&path) {
+                            print!("Unable to save preset: {}", err)
+                        }
+                }
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button("Output folder...").clicked() {
+                    if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                        self.batch_output = Some(path);
+                    }
+                }
+                ui.label(self.batch_output.as_ref().map_or_else(|| "none chosen".to_string(), |path| path.display().to_string()));
+            });
+
+            ui.horizontal_wrapped(|ui| {
+                for export in Export::ALL {
+                    let mut chosen = self.batch_exports.contains(&export);
+                    if ui.checkbox(&mut chosen, export.name()).changed() {
+                        self.batch_exports.retain(|other| *other != export);
+                        if chosen {
+                            self.batch_exports.push(export);
+                        }
+                    }
+                }
+            });
+
+            match &self.batch {
+                Some(batch) => {
+                    let fraction = batch.finished() as f32 / batch.total.max(1) as f32;
+                    ui.add(egui::ProgressBar::new(fraction).text(format!("{} of {} images", batch.finished(), batch.total)));
+                },
+                None => {
+                    let ready = !self.batch_input.trim().is_empty() && self.batch_output.is_some();
+                    if ui.add_enabled(ready, egui::Button::new("Convert")).clicked() {
+                        self.start_batch();
+                    }
+                },
+            }
+
+            if !self.batch_message.is_empty() {
+                ui.label(&self.batch_message);
+            }
+            for result in &self.batch_results {
+                if let Err(err) = &result.summary {
+                    ui.colored_label(ui.visuals().warn_fg_color, format!("{}: {}", result.path.display(), err));
+                }
+            }
+        });
+    }
+
+    fn lettering_ui(&mut self, ui: &mut Ui) {
+        let Some(chart_data) = &self.chart_data else {
+            return;
+        };
+
+        let mut preview_changed = false;
+        let mut decorations_changed = false;
+        ui.collapsing("Lettering", |ui| {
+            preview_changed |= ui.add(egui::TextEdit::multiline(&mut self.lettering.text).hint_text("Text").desired_rows(2)).changed();
+
+            let typeface_name = |typeface| match typeface {
+                Typeface::Builtin(alphabet) => Alphabet::name(alphabet),
+                Typeface::TrueType => "TrueType font",
+            };
+            let typeface = self.lettering.typeface;
+            egui::ComboBox::from_label("Font")
+                .selected_text(typeface_name(typeface))
+                .show_ui(ui, |ui| {
+                    for typeface in Alphabet::ALL.map(Typeface::Builtin).into_iter().chain([Typeface::TrueType]) {
+                        ui.selectable_value(&mut self.lettering.typeface, typeface, typeface_name(typeface));
+                    }
+                });
+            preview_changed |= typeface != self.lettering.typeface;
+
+            if self.lettering.typeface == Typeface::TrueType {
+                ui.horizontal(|ui| {
+                    if ui.button("Load font...").clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("fonts", &["ttf", "otf"])
+                            .pick_file() {
+                                match TrueTypeFont::load(&path) {
+                                    Ok(font) => {
+                                        self.lettering.font = Some(font);
+                                        preview_changed = true;
+                                    },
+                                    Err(err) => print!("Unable to load font: {}", err),
+                                }
+                        }
+                    }
+                    ui.label(self.lettering.font.as_ref().map_or("No font loaded", |font| &font.name));
+                });
+                preview_changed |= ui.add(egui::Slider::new(&mut self.lettering.font_height, 5..=40).text("Height in stitches")).changed();
+            }
+
+            let catalog = self.config.craft.catalog();
+            let floss = catalog.find(self.lettering.floss_code.trim());
+            ui.horizontal(|ui| {
+                preview_changed |= ui.add(egui::TextEdit::singleline(&mut self.lettering.floss_code).hint_text(catalog.code_name()).desired_width(60.0)).changed();
+                match floss {
+                    Some(floss) => {
+                        renderer::color_swatch(ui, floss.color());
+                        ui.label(floss.to_string());
+                    },
+                    None => {
+                        ui.colored_label(ui.visuals().warn_fg_color, "Unknown floss");
+                    },
+                }
+            });
+
+            if self.chart_tool == ChartTool::PlaceText {
+                ui.horizontal(|ui| {
+                    ui.label("Click the chart to place the text");
+                    if ui.button("Cancel").clicked() {
+                        self.chart_tool = ChartTool::Inspect;
+                        preview_changed = true;
+                    }
+                });
+            } else {
+                let ready = floss.is_some() && self.lettering.render(chart_data.geometry.aspect).is_some();
+                if ui.add_enabled(ready, egui::Button::new("Place on chart")).clicked() {
+                    self.chart_tool = ChartTool::PlaceText;
+                }
+            }
+
+            let mut removed = None;
+            for (i, text) in self.decorations.texts.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("\"{}\" in {} at ({}, {})", text.text.replace('\n', " "), text.floss, text.x + 1, text.y + 1));
+                    if ui.small_button("Remove").clicked() {
+                        removed = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = removed {
+                self.decorations.texts.remove(i);
+                decorations_changed = true;
+            }
+        });
+
+        if decorations_changed {
+            self.apply_decorations();
+        } else if preview_changed {
+            self.update_overlays();
+        }
+    }
+}
+
+fn percent(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        100.0 * part as f64 / total as f64
+    }
+}
+
+impl eframe::App for Cross {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        match self.worker.poll() {
+            Some(Output::Pattern(analysis)) => {
+                let (chart_data, metrics, pruned_colors) = match analysis {
+                    Some((chart_data, metrics, pruned_colors)) => (Some(chart_data), Some(metrics), pruned_colors),
+                    None => (None, None, vec![]),
+                };
+                self.metrics = metrics;
+                self.pruned_colors = pruned_colors;
+                self.set_chart_data(chart_data);
+            },
+            Some(Output::ColorCurve(color_curve)) => self.color_curve = color_curve,
+            None => {},
+        }
+
+        if let Some(batch) = &mut self.batch {
+            if let Some(results) = batch.poll() {
+                match results {
+                    Ok(results) => {
+                        let converted = results.iter().filter(|result| result.summary.is_ok()).count();
+                        self.batch_message = format!("Converted {} of {} images. Summary saved to {}", converted, results.len(), batch.report_path.display());
+                        self.batch_results = results;
+                    },
+                    Err(err) => {
+                        self.batch_message = format!("Batch failed: {}", err);
+                        self.batch_results.clear();
+                    },
+                }
+                self.batch = None;
+            }
+        }
+
+        if self.worker.is_busy() || self.batch.is_some() {
+            // Keep checking in on the worker, even if the user isn't interacting with the window.
+            ctx.request_repaint_after(Duration::from_millis(50));
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal_centered(|ui| {    
+                ui.vertical(|ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        // Image loading and rendering    
+                        if ui.button("Select Image file...").clicked() {
+                            if let Some(path) = rfd::FileDialog::new()
+                                .add_filter("images", &["jpg", "jpeg", "png"])
+                                .pick_file() {
+                                    if self.load_image(&path, ui.ctx()) {
+                                        self.run_analysis();
+                                    }
+                            }
+                        }
+
+                        ui.horizontal(|ui| {
+                            if ui.button("Open project...").clicked() {
+                                if let Some(path) = rfd::FileDialog::new()
+                                    .add_filter("project", &["json"])
+                                    .pick_file() {
+                                        self.open_project(&path, ui.ctx());
+                                }
+                            }
+
+                            if ui.add_enabled(self.chart_data.is_some(), egui::Button::new("Save project...")).clicked() {
+                                if let Some(path) = rfd::FileDialog::new()
+                                    .add_filter("project", &["json"])
+                                    .set_file_name("pattern.json")
+                                    .save_file() {
+                                        self.save_project(&path);
+                                }
+                            }
+                        });
+
+                        if self.image.size[0] != 0 {
+                            ui.label(format!("Image size: [{},{}]", self.image.size[0], self.image.size[1]));
+                        }
+
+                        let mut mask_changed = false;
+                        if let Some(texture) = &self.texture {
+                            // Scale image down to 200x(aspect-ratio)
+                            let width = 200.0;
+                            let height = width *
+                                (self.image.size[1] as f32 / self.image.size[0] as f32);
+                            let sense = if self.painting_mask { egui::Sense::click_and_drag() } else { egui::Sense::hover() };
+                            let response = ui.add(egui::Image::new(texture, egui::Vec2::new(width, height)).sense(sense));
+                            if let Some(mask_texture) = &self.mask_texture {
+                                let full_uv = egui::Rect::from_min_max(egui::Pos2::ZERO, egui::Pos2::new(1.0, 1.0));
+                                ui.painter().image(mask_texture.id(), response.rect, full_uv, egui::Color32::WHITE);
+                            }
+
+                            // Left button marks, right button erases.
+                            if response.is_pointer_button_down_on() {
+                                if let Some(pos) = response.interact_pointer_pos() {
+                                    let uv = (pos - response.rect.min) / response.rect.size();
+                                    let important = !ui.input(|i| i.pointer.secondary_down());
+                                    self.mask.paint(uv.x, uv.y, self.brush_size, important);
+                                    mask_changed = true;
+                                }
+                            }
+
+                            ui.horizontal(|ui| {
+                                ui.checkbox(&mut self.painting_mask, "Paint important areas")
+                                    .on_hover_text("Drag over the image to mark areas that should keep their detail. Right-drag erases.");
+                                if ui.add_enabled(!self.mask.is_empty(), egui::Button::new("Clear")).clicked() {
+                                    self.mask.clear();
+                                    mask_changed = true;
+                                }
+                            });
+                            if self.painting_mask {
+                                ui.add(egui::Slider::new(&mut self.brush_size, 0.01..=0.2).text("Brush size"));
+                            }
+                        }
+
+                        if mask_changed {
+                            self.update_mask_texture(ui.ctx());
+                        }
+
+                        // Generation controls
+                        egui::ComboBox::from_label("Craft")
+                            .selected_text(self.config.craft.name())
+                            .show_ui(ui, |ui| {
+                                for craft in Craft::ALL {
+                                    ui.selectable_value(&mut self.config.craft, craft, craft.name());
+                                }
+                            });
+                        if self.config.craft == Craft::Knitting {
+                            ui.horizontal(|ui| {
+                                ui.label("Gauge per 10cm:");
+                                ui.add(egui::DragValue::new(&mut self.config.knit_stitches).clamp_range(config::KNIT_STITCHES_RANGE).suffix(" stitches"));
+                                ui.add(egui::DragValue::new(&mut self.config.knit_rows).clamp_range(config::KNIT_ROWS_RANGE).suffix(" rows"));
+                            });
+                        }
+
+                        ui.add_enabled(self.pixel_grid.is_some() || self.config.pixel_art, egui::Checkbox::new(&mut self.config.pixel_art, "Pixel art"))
+                            .on_hover_text("One stitch per art pixel, in the image's exact colors if there are few enough");
+                        let pixel_grid = self.pixel_grid.filter(|_| self.config.pixel_art);
+                        if let Some([width, height]) = pixel_grid {
+                            self.config.num_width = width as i32;
+                            self.config.num_height = height as i32;
+                        }
+
+                        ui.add_enabled_ui(pixel_grid.is_none(), |ui| {
+                            ui.add(egui::Slider::new(&mut self.config.num_width, config::SIZE_RANGE).text("Width"));
+                            ui.add(egui::Slider::new(&mut self.config.num_height, config::SIZE_RANGE).text("Height"));
+                            ui.add(egui::Slider::new(&mut self.config.num_days, config::DAYS_RANGE).text("Days"));
+                            if ui.add_enabled(self.image.size[0] != 0, egui::Button::new("Match image proportions")).clicked() {
+                                // Cells aren't always square, so the row count also depends on the cell shape.
+                                let [cell_width, cell_height] = self.config.craft.cell_mm(&self.config);
+                                let image_aspect = self.image.size[1] as f32 / self.image.size[0] as f32;
+                                let height = (self.config.num_width as f32 * image_aspect * cell_width / cell_height).round() as i32;
+                                self.config.num_height = height.clamp(*config::SIZE_RANGE.start(), *config::SIZE_RANGE.end());
+                            }
+                        });
+
+                        let [cell_width, cell_height] = self.config.craft.cell_mm(&self.config);
+                        ui.label(format!("Finished size: about {:.0} x {:.0} cm",
+                            self.config.num_width as f32 * cell_width / 10.0,
+                            self.config.num_height as f32 * cell_height / 10.0));
+                        ui.label("Colorization settings");
+                        ui.add_enabled(self.config.fixed_palette.is_empty(), egui::Slider::new(&mut self.config.num_colors, config::COLORS_RANGE).text("Colors"))
+                            .on_disabled_hover_text("The imported palette sets the colors");
+                        ui.add(egui::Slider::new(&mut self.config.num_iterations, config::ITERATIONS_RANGE).text("kNN Iterations"));
+                        ui.add(egui::Slider::new(&mut self.config.confetti_neighbors, config::CONFETTI_RANGE).text("Confetti cleanup"));
+                        ui.add(egui::Slider::new(&mut self.config.min_color_stitches, config::MIN_COLOR_STITCHES_RANGE).text("Minimum stitches per color"))
+                            .on_hover_text("Colors with fewer stitches are merged into the closest remaining color");
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut self.config.merge_duplicates, "Merge near-duplicates")
+                                .on_hover_text("Merges colors whose threads are hard to tell apart into the more used one");
+                            ui.add(egui::DragValue::new(&mut self.config.duplicate_delta_e).clamp_range(config::DUPLICATE_DELTA_E_RANGE).speed(0.1).prefix("below ΔE "));
+                        });
+                        ui.add_enabled(self.config.craft.allows_blends(), egui::Checkbox::new(&mut self.config.use_blends, "Blend two flosses"))
+                            .on_hover_text("Allow one strand each of two flosses where no single floss is close");
+                        ui.horizontal(|ui| {
+                            ui.add(egui::DragValue::new(&mut self.config.seed).prefix("Seed: "));
+                            if ui.button("Randomize").clicked() {
+                                self.config.seed = rand::random();
+                            }
+                        });
+
+                        self.target_palette_ui(ui);
+                        self.color_count_ui(ui);
+                        let stash_changed = self.stash_ui(ui);
+                        if self.config.recalculate_columns() || stash_changed || mask_changed {
+                            self.run_analysis();
+                        }
+
+                        if self.worker.is_busy() {
+                            ui.horizontal(|ui| {
+                                ui.spinner();
+                                ui.label("Analyzing...");
+                            });
+                        }
+
+                        self.palette_ui(ui);
+                        self.quality_ui(ui);
+                        self.schedule_ui(ui);
+                        self.stitch_order_ui(ui);
+                        self.progress_ui(ui);
+                        self.lettering_ui(ui);
+                        self.repeat_ui(ui);
+                        self.backstitch_ui(ui);
+                        self.border_ui(ui);
+                        self.colorway_ui(ui);
+                        self.chart_export_ui(ui);
+                        self.embroidery_ui(ui);
+                        self.batch_ui(ui);
+
+                        // TODO add aspect ratio lock
+                        // TODO add config for the output generation
+                    });
                 });
 
                 // Cross-stitch chart
                 ui.vertical(|ui| {
+                    let mut hovered_stitch = None;
+                    let mut clicked_stitch = None;
+                    let mut hovered_corner = None;
+                    let mut clicked_corner = None;
                     if let Some(chart_data) = &self.chart_data {
-                        renderer::render_chart(ui, chart_data);
+                        let chart_response = self.chart.show(ui, chart_data);
+                        hovered_stitch = chart_response.hovered_stitch;
+                        clicked_stitch = hovered_stitch.filter(|_| chart_response.response.clicked());
+                        hovered_corner = chart_response.hovered_corner;
+                        clicked_corner = hovered_corner.filter(|_| chart_response.response.clicked());
                     }
 
-                    if let Some(handle) = &self.process_handle {
-                        if handle.is_finished() {
-                            self.cancel_sender = None;
-                            match self.process_handle.take().expect("make less confusing.").join() {
-                                Ok(points) =>
-                                {
-                                    self.chart_data = Some(ChartData { points: points });
-                                    self.has_finished = true; 
-                                },
-                                Err(_) => {}
-                            };
+                    let shift = ui.input(|i| i.modifiers.shift);
+                    match (self.chart_tool, clicked_corner, clicked_stitch) {
+                        (ChartTool::Backstitch, Some(corner), _) => self.click_corner(corner, shift),
+                        (ChartTool::Backstitch, None, _) => {},
+                        (_, _, Some(stitch)) => self.click_chart(stitch, shift),
+                        _ => {},
+                    }
 
-                            self.process_handle = None;
-                        }
+                    // The backstitch line being drawn follows the pointer.
+                    let preview_corner = hovered_corner.filter(|_| self.chart_tool == ChartTool::Backstitch && self.backstitch_start.is_some());
+                    if preview_corner != self.preview_corner {
+                        self.preview_corner = preview_corner;
+                        self.update_overlays();
+                    }
+
+                    // Placing text and marking a tile preview what a click would do.
+                    let previewing = matches!(self.chart_tool, ChartTool::PlaceText | ChartTool::MarkTile);
+                    let preview_stitch = hovered_stitch.filter(|_| previewing);
+                    if preview_stitch != self.preview_stitch {
+                        self.preview_stitch = preview_stitch;
+                        self.update_overlays();
                     }
                 });
             });
@@ -1,11 +1,39 @@
 //! Performs separate-threaded analysis of images
+//!
+//! Analysis runs as a chain of stages: preprocess → resample → quantize → cleanup → floss mapping.
+//! Each stage remembers the inputs its last output was computed from, so a settings change only
+//! re-runs the stages downstream of it.
+use crate::egui::Color32;
 use crate::egui::ColorImage;
 use crate::egui::Rgba;
 
-use std::sync::mpsc;
-use clustering;
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
+use super::color::Lab;
 use super::config::Config;
+use super::craft::{Craft, Offset};
+use super::floss::{self, Catalog, Floss};
+use super::mask::Mask;
+use super::renderer::{ChartData, PaletteColor};
+
+mod cleanup;
+mod duplicates;
+pub use duplicates::{near_duplicates, DuplicatePair};
+mod kmeans;
+use kmeans::Clusters;
+mod metrics;
+pub use metrics::Metrics;
+mod pixel_art;
+pub use pixel_art::native_grid;
+mod prune;
+pub use prune::PrunedColor;
+mod suggest;
+pub use suggest::ColorCurve;
+
+// How much more a masked stitch counts than an unmasked one when picking colors.
+const MASKED_WEIGHT: f32 = 5.0;
 
 // Doc comments: https://doc.rust-lang.org/reference/comments.html#:~:text=Comments%20in%20Rust%20code%20follow%20the%20general%20C%2B%2B,comments%20are%20interpreted%20as%20a%20form%20of%20whitespace.
 
@@ -13,129 +41,520 @@ use super::config::Config;
 // https://doc.rust-lang.org/rust-by-example/trait/derive.html
 #[derive(Clone, PartialEq)]
 pub struct ColorPoint {
-    pub x: f64,
-    pub y: f64,
+    // Stitch column and row
+    pub x: usize,
+    pub y: usize,
     pub c: Rgba,
 }
 
-impl clustering::Elem for ColorPoint {
-    fn dimensions(&self) -> usize {
-        3
+/// Lets a running analysis notice that a newer job has superseded it.
+#[derive(Clone)]
+pub struct Cancellation {
+    latest_job: Arc<AtomicU64>,
+    job_id: u64,
+}
+
+impl Cancellation {
+    pub fn new(latest_job: Arc<AtomicU64>, job_id: u64) -> Self {
+        Cancellation { latest_job, job_id }
     }
 
-    fn at(&self, i: usize)  -> f64 {
-        self.c[i] as f64
+    /// For analyses that always run to completion, such as batches.
+    pub fn never() -> Self {
+        Cancellation { latest_job: Arc::new(AtomicU64::new(0)), job_id: 0 }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.latest_job.load(Ordering::Relaxed) != self.job_id
     }
 }
 
-pub struct AvgColor {
-    pub avg: [f32;3],
-    pub num: i32,
+// Stage keys. Each includes the key of the stage before it, so upstream changes invalidate everything after.
+#[derive(Clone, PartialEq)]
+struct ResampleKey {
+    image_id: u64,
+    width: i32,
+    height: i32,
+    pixel_art: bool,
+    craft: Craft,
+}
+
+#[derive(Clone, PartialEq)]
+struct QuantizeKey {
+    resample: ResampleKey,
+    num_colors: i32,
+    num_iterations: i32,
+    seed: u64,
+    mask: Option<Mask>,
+    fixed_palette: Vec<[u8; 3]>,
+}
+
+#[derive(Clone, PartialEq)]
+struct CleanupKey {
+    quantize: QuantizeKey,
+    confetti_neighbors: i32,
 }
 
-impl AvgColor {
+#[derive(Clone, PartialEq)]
+struct MappingKey {
+    quantize: QuantizeKey,
+
+    // Floss codes the palette is limited to, if any
+    stash: Option<Vec<&'static str>>,
+    use_blends: bool,
+}
+
+/// Floss palette, and the palette index each cluster is stitched with.
+struct Mapping {
+    palette: Vec<PaletteColor>,
+    cluster_colors: Vec<usize>,
+}
+
+/// The output of one stage, along with the inputs it was computed from.
+struct Stage<K, V> {
+    cached: Option<(K, V)>,
+
+    // How many times the stage has run, so tests can tell a cached output from a fresh one
+    #[cfg(test)]
+    runs: usize,
+}
+
+impl<K: PartialEq, V> Stage<K, V> {
     fn new() -> Self {
-        AvgColor {
-            avg: [0.0, 0.0, 0.0],
-            num: 0,
+        Stage {
+            cached: None,
+            #[cfg(test)]
+            runs: 0,
         }
     }
 
-    fn add_color(&mut self, c: Rgba) {
-        self.avg[0] += c[0];
-        self.avg[1] += c[1];
-        self.avg[2] += c[2];
-        self.num += 1;
+    /// Returns the cached output if it was computed from the same inputs, otherwise runs the stage.
+    /// Nothing is cached if the stage is cancelled.
+    fn get_or_run(&mut self, key: K, run: impl FnOnce() -> Option<V>) -> Option<&V> {
+        let is_current = matches!(&self.cached, Some((cached_key, _)) if *cached_key == key);
+        if !is_current {
+            self.cached = None;
+            #[cfg(test)]
+            {
+                self.runs += 1;
+            }
+            self.cached = Some((key, run()?));
+        }
+
+        self.cached.as_ref().map(|(_, value)| value)
     }
+}
 
-    fn compute_average(&mut self) {
-        if self.num != 0 {
-            self.avg[0] /= self.num as f32;
-            self.avg[1] /= self.num as f32;
-            self.avg[2] /= self.num as f32;
+pub struct Pipeline {
+    preprocessed: Stage<u64, Vec<Rgba>>,
+    resampled: Stage<ResampleKey, Vec<ColorPoint>>,
+    quantized: Stage<QuantizeKey, Clusters>,
+    cleaned: Stage<CleanupKey, Vec<usize>>,
+    mapped: Stage<MappingKey, Mapping>,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self {
+            preprocessed: Stage::new(),
+            resampled: Stage::new(),
+            quantized: Stage::new(),
+            cleaned: Stage::new(),
+            mapped: Stage::new(),
         }
     }
 }
 
-pub fn update_pattern(image: ColorImage, config: Config, canceller: mpsc::Receiver<bool>) -> Vec<ColorPoint> {
-    let mut points = Vec::new();
+impl Pipeline {
+    /// `image_id` must change whenever a different image is loaded.
+    /// Returns None if the analysis was cancelled or there is no image to analyze.
+    /// Identical images and configs always produce identical charts.
+    /// If a stash is given, only those flosses are used. Masked areas get extra say in the palette and keep their details.
+    /// The chart comes with metrics of how closely it matches the image, and the colors pruned for being barely used.
+    pub fn update_pattern(&mut self, image: &ColorImage, image_id: u64, config: &Config, stash: Option<&[&'static Floss]>, mask: Option<&Mask>, cancellation: &Cancellation) -> Option<(ChartData, Metrics, Vec<PrunedColor>)> {
+        if image.size[0] == 0 {
+            return None;
+        }
+
+        let resample_key = ResampleKey {
+            image_id,
+            width: config.num_width,
+            height: config.num_height,
+            pixel_art: config.pixel_art,
+            craft: config.craft,
+        };
+        let quantize_key = QuantizeKey {
+            resample: resample_key.clone(),
+            num_colors: config.num_colors,
+            num_iterations: config.num_iterations,
+            seed: config.seed,
+            mask: mask.cloned(),
+            fixed_palette: config.fixed_palette.clone(),
+        };
+        let cleanup_key = CleanupKey {
+            quantize: quantize_key.clone(),
+            confetti_neighbors: config.confetti_neighbors,
+        };
+        let mapping_key = MappingKey {
+            quantize: quantize_key.clone(),
+            stash: stash.map(|stash| stash.iter().map(|floss| floss.code).collect()),
+            use_blends: use_blends(config),
+        };
+
+        let preprocessed = self.preprocessed.get_or_run(image_id, || Some(preprocess(image)))?;
+        let resampled = self.resampled.get_or_run(resample_key, || Some(pass_through(image.size, preprocessed, config)))?;
+        if cancellation.is_cancelled() {
+            return None;
+        }
+
+        let protected = protected_stitches(config, mask);
+        let clusters = self.quantized.get_or_run(quantize_key.clone(), || limit_colors(config, resampled, &protected, cancellation))?;
+        let stitches = self.cleaned.get_or_run(cleanup_key, || Some(cleanup::remove_confetti(
+            &clusters.membership,
+            &protected,
+            config.num_width as usize,
+            config.num_height as usize,
+            config.confetti_neighbors as usize)))?;
+        let mapping = self.mapped.get_or_run(mapping_key, || {
+            Some(map_to_floss(&clusters.centroids, &Threads::new(config.craft.catalog(), stash, use_blends(config)), clusters.exact))
+        })?;
 
-    // Config If: Take points then constrict to color limit.
-    pass_through(image, &config, &mut points, &canceller);
-    if points.len() > 0 {
-        let limited_points = limit_colors(&config, &mut points, &canceller);
-        limited_points
-    } else {
-        points
+        let mut chart_data = ChartData {
+            width: config.num_width as usize,
+            height: config.num_height as usize,
+            geometry: config.craft.geometry(config),
+            catalog: config.craft.catalog(),
+            palette: mapping.palette.clone(),
+            stitches: stitches.iter().map(|cluster| mapping.cluster_colors[*cluster]).collect(),
+            backstitches: vec![],
+        };
+        if config.merge_duplicates {
+            duplicates::merge_near_duplicates(&mut chart_data, config.duplicate_delta_e);
+        }
+        let pruned = prune::prune(&mut chart_data, &protected, config.min_color_stitches as usize);
+        let metrics = metrics::measure(&chart_data, resampled);
+        Some((chart_data, metrics, pruned))
+    }
+
+    /// Charts the image at a range of color counts, to suggest how many it needs.
+    /// Returns None if the sweep was cancelled or there is no image to analyze.
+    pub fn color_curve(&mut self, image: &ColorImage, image_id: u64, config: &Config, stash: Option<&[&'static Floss]>, mask: Option<&Mask>, cancellation: &Cancellation) -> Option<ColorCurve> {
+        if image.size[0] == 0 {
+            return None;
+        }
+
+        let resample_key = ResampleKey {
+            image_id,
+            width: config.num_width,
+            height: config.num_height,
+            pixel_art: config.pixel_art,
+            craft: config.craft,
+        };
+        let preprocessed = self.preprocessed.get_or_run(image_id, || Some(preprocess(image)))?;
+        let resampled = self.resampled.get_or_run(resample_key, || Some(pass_through(image.size, preprocessed, config)))?;
+        suggest::sweep(resampled, config, stash, &protected_stitches(config, mask), cancellation)
     }
 }
 
-fn limit_colors(config: &Config, points: &mut Vec<ColorPoint>, canceller: &mpsc::Receiver<bool>) -> Vec<ColorPoint> {
-    // Config If: Find-closest and merge
-    // No need to reinvent the wheel, can use kmeans clustering
+/// Flattens transparency onto white fabric and converts to linear color.
+fn preprocess(image: &ColorImage) -> Vec<Rgba> {
+    image.pixels.par_iter().map(|pixel| {
+        let color = Rgba::from(*pixel);
+        let fabric = 1.0 - color.a();
+        Rgba::from_rgb(color.r() + fabric, color.g() + fabric, color.b() + fabric)
+    }).collect()
+}
+
+fn use_blends(config: &Config) -> bool {
+    config.use_blends && config.craft.allows_blends()
+}
 
-    // Cluster
-    let mut limited_points = Vec::new();
-    let clusters = clustering::kmeans(config.num_colors as usize, &points, 100); // max-iters
-    match canceller.try_recv() {
-        Ok(_) => return limited_points,
-        Err(_) => {}
+/// Whether each stitch is in the masked area, row-major.
+fn protected_stitches(config: &Config, mask: Option<&Mask>) -> Vec<bool> {
+    let (width, height) = (config.num_width as usize, config.num_height as usize);
+    match mask {
+        Some(mask) => mask.sample(width, height),
+        None => vec![false; width * height],
     }
-    // print!("Computed a total of {} clusters\n", clusters.centroids.len());
+}
 
-    // Find average colors for each cluster
-    let mut avg_cluster_colors = vec![];
-    for _ in 0..clusters.centroids.len() {
-        avg_cluster_colors.push(AvgColor::new());
+fn limit_colors(config: &Config, points: &[ColorPoint], protected: &[bool], cancellation: &Cancellation) -> Option<Clusters> {
+    if !config.fixed_palette.is_empty() {
+        return Some(nearest_fixed_color(points, &config.fixed_palette));
     }
 
-    for i in 0..clusters.membership.len() {
-        let cluster_id = clusters.membership[i];
-        avg_cluster_colors[cluster_id].add_color(clusters.elements[i].c);
+    // Pixel art with few enough colors is charted in exactly its own colors.
+    if config.pixel_art {
+        if let Some(clusters) = pixel_art::exact_palette(points, config.num_colors as usize) {
+            return Some(clusters);
+        }
     }
-    
-    for i in 0..clusters.centroids.len() {
-        avg_cluster_colors[i].compute_average();
+
+    // Config If: Find-closest and merge
+    let colors: Vec<[f32; 3]> = points.par_iter().map(|p| [p.c.r(), p.c.g(), p.c.b()]).collect();
+    let weights: Vec<f32> = protected.iter().map(|protected| if *protected { MASKED_WEIGHT } else { 1.0 }).collect();
+    kmeans::kmeans(
+        &colors,
+        &weights,
+        config.num_colors as usize,
+        config.num_iterations as usize,
+        config.seed,
+        cancellation)
+}
+
+/// Each stitch takes the perceptually closest color of an imported palette. Palette colors no stitch takes are dropped.
+fn nearest_fixed_color(points: &[ColorPoint], fixed_palette: &[[u8; 3]]) -> Clusters {
+    let labs: Vec<Lab> = fixed_palette.iter().map(|[r, g, b]| Lab::from(Color32::from_rgb(*r, *g, *b))).collect();
+    let nearest: Vec<usize> = points.par_iter().map(|point| {
+        let lab = Lab::from(point.c);
+        (0..labs.len()).min_by(|a, b| lab.delta_e(&labs[*a]).total_cmp(&lab.delta_e(&labs[*b]))).expect("fixed palette isn't empty")
+    }).collect();
+
+    let mut used: Vec<usize> = nearest.clone();
+    used.sort_unstable();
+    used.dedup();
+
+    Clusters {
+        centroids: used.iter().map(|i| {
+            let [r, g, b] = fixed_palette[*i];
+            let color = Rgba::from(Color32::from_rgb(r, g, b));
+            [color.r(), color.g(), color.b()]
+        }).collect(),
+        membership: nearest.iter().map(|i| used.binary_search(i).expect("every nearest color is used")).collect(),
+        exact: false,
     }
+}
 
-    // Expand out the clusters into a new set of colors
-    for i in 0..clusters.elements.len() {
-        let cluster_id = clusters.membership[i];
-        let cluster_color = avg_cluster_colors[cluster_id].avg;
-        limited_points.push(ColorPoint { 
-            x: clusters.elements[i].x,
-            y: clusters.elements[i].y,
-            c: Rgba::from_rgb(cluster_color[0], cluster_color[1], cluster_color[2])});
+fn centroid_color(centroid: &[f32; 3]) -> Color32 {
+    Color32::from(Rgba::from_rgb(centroid[0], centroid[1], centroid[2]))
+}
+
+// Blends are only used when they look noticeably closer than the best single floss, as they are fiddlier to stitch.
+const BLEND_MIN_IMPROVEMENT: f32 = 2.3;
+
+/// A single floss or a blend of two, as it looks stitched
+struct Candidate {
+    thread: PaletteColor,
+    lab: Lab,
+}
+
+/// Everything a color can be stitched with: each floss of the catalog, limited to a stash if given, and blends of two if allowed.
+/// An empty stash doesn't limit anything.
+struct Threads {
+    candidates: Vec<Candidate>,
+
+    // Only the stash's flosses are candidates
+    limited: bool,
+}
+
+impl Threads {
+    fn new(catalog: Catalog, stash: Option<&[&'static Floss]>, use_blends: bool) -> Self {
+        let stash = stash.filter(|stash| !stash.is_empty());
+        let flosses: Vec<&'static Floss> = match stash {
+            Some(stash) => stash.to_vec(),
+            None => catalog.flosses().iter().collect(),
+        };
+
+        let mut candidates: Vec<Candidate> = flosses.iter().map(|floss| Candidate {
+            thread: PaletteColor::new(floss.color(), floss),
+            lab: floss.lab(),
+        }).collect();
+
+        if use_blends {
+            for (i, floss) in flosses.iter().enumerate() {
+                for blend in &flosses[i + 1..] {
+                    let color = floss::blend_color(floss, blend);
+                    candidates.push(Candidate {
+                        thread: PaletteColor { color, floss, blend: Some(blend) },
+                        lab: Lab::from(color),
+                    });
+                }
+            }
+        }
+
+        Threads { candidates, limited: stash.is_some() }
     }
+}
 
-    limited_points
+fn closest_thread(color: Color32, candidates: &[Candidate]) -> &PaletteColor {
+    let target = Lab::from(color);
+    let closest = |blended: bool| candidates.iter()
+        .filter(|candidate| candidate.thread.blend.is_some() == blended)
+        .map(|candidate| (candidate, target.delta_e(&candidate.lab)))
+        .min_by(|a, b| a.1.total_cmp(&b.1));
+
+    let (single, single_error) = closest(false).expect("there is always at least one floss to choose from");
+    match closest(true) {
+        Some((blend, blend_error)) if blend_error + BLEND_MIN_IMPROVEMENT < single_error => &blend.thread,
+        _ => &single.thread,
+    }
 }
 
-fn pass_through(image: ColorImage, config: &Config, points: &mut Vec<ColorPoint>, canceller: &mpsc::Receiver<bool>) {
-    if image.size[0] == 0 {
-        return
+/// Each cluster is stitched with the closest matching floss, or blend of two flosses.
+/// Limited to a stash, or when snapping, clusters are shown in their thread's color, and clusters that land on
+/// the same thread share a palette color. Otherwise each cluster keeps its own color.
+fn map_to_floss(centroids: &[[f32; 3]], threads: &Threads, snap: bool) -> Mapping {
+    let cluster_threads: Vec<(Color32, &PaletteColor)> = centroids.par_iter().map(|centroid| {
+        let color = centroid_color(centroid);
+        (color, closest_thread(color, &threads.candidates))
+    }).collect();
+
+    if !threads.limited && !snap {
+        return Mapping {
+            palette: cluster_threads.into_iter().map(|(color, thread)| PaletteColor { color, ..thread.clone() }).collect(),
+            cluster_colors: (0..centroids.len()).collect(),
+        };
     }
 
-    // Iterate in floating point to avoid rounding errors that generate slightly more expected points.
-    let y_step = image.size[1] as f64 / (config.num_height as f64);
-    let x_step = image.size[0] as f64 / (config.num_width as f64);
-    
-    for y in 0..config.num_height {
-        // Early-exit if any data received.
-        match canceller.try_recv() {
-            Ok(_) => return,
-            Err(_) => {}
+    let mut palette: Vec<PaletteColor> = vec![];
+    let cluster_colors = cluster_threads.into_iter().map(|(_, thread)| {
+        let same_thread = |color: &PaletteColor| color.floss.code == thread.floss.code
+            && color.blend.map(|blend| blend.code) == thread.blend.map(|blend| blend.code);
+        match palette.iter().position(same_thread) {
+            Some(palette_index) => palette_index,
+            None => {
+                palette.push(thread.clone());
+                palette.len() - 1
+            }
         }
+    }).collect();
+
+    Mapping { palette, cluster_colors }
+}
 
-        for x in 0..config.num_width {
+fn pass_through(image_size: [usize; 2], pixels: &[Rgba], config: &Config) -> Vec<ColorPoint> {
+    // Iterate in floating point to avoid rounding errors that generate slightly more expected points.
+    let y_step = image_size[1] as f64 / (config.num_height as f64);
+    let x_step = image_size[0] as f64 / (config.num_width as f64);
+
+    // Pixel art is sampled from the middle of each art pixel, so being slightly off the grid doesn't pick up a neighbor.
+    let offset = if config.pixel_art { 0.5 } else { 0.0 };
+
+    // Offset grids sample alternate rows or columns half a cell along.
+    let grid_offset = config.craft.geometry(config).offset;
+    let shift = |i: usize, offset_grid: Offset| if grid_offset == offset_grid && i % 2 == 1 { 0.5 } else { 0.0 };
+
+    // Rows are sampled in parallel, but collected in order.
+    (0..config.num_height as usize).into_par_iter().flat_map_iter(|y| {
+        (0..config.num_width as usize).map(move |x| {
             // Round steps to avoid scrolling issues at image ends.
-            let x_eff = (x_step * (x as f64)) as usize;
-            let y_eff = (y_step * (y as f64)) as usize;
-            let color = image.pixels[x_eff + y_eff * image.size[0]];
-            points.push(ColorPoint { 
-                x: x_eff as f64,
-                y: (y_eff as f64)*-1.0, 
-                c: Rgba::from(color)});
+            let x_eff = ((x_step * (x as f64 + offset + shift(y, Offset::Rows))) as usize).min(image_size[0] - 1);
+            let y_eff = ((y_step * (y as f64 + offset + shift(x, Offset::Columns))) as usize).min(image_size[1] - 1);
+            ColorPoint {
+                x,
+                y,
+                c: pixels[x_eff + y_eff * image_size[0]]}
+        })
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A smooth gradient with a few sharp shapes, so quantizing it has real choices to make.
+    fn test_image() -> ColorImage {
+        let (width, height) = (120, 90);
+        let pixels = (0..width * height).map(|i| {
+            let (x, y) = (i % width, i / width);
+            if (x / 20 + y / 15) % 5 == 0 {
+                Color32::from_rgb(200, 30, 40)
+            } else {
+                Color32::from_rgb((x * 2) as u8, (y * 2) as u8, ((x + y) % 256) as u8)
+            }
+        }).collect();
+        ColorImage { size: [width, height], pixels }
+    }
+
+    /// Each palette color's threads and exact color, then every stitch's palette index.
+    fn chart_fingerprint(chart_data: &ChartData) -> (Vec<String>, Vec<usize>) {
+        let palette = chart_data.palette.iter().map(|color| format!("{} {:?}", color, color.color.to_array())).collect();
+        (palette, chart_data.stitches.clone())
+    }
+
+    fn analyze(pipeline: &mut Pipeline, image: &ColorImage, config: &Config) -> ChartData {
+        pipeline.update_pattern(image, 1, config, None, None, &Cancellation::never()).expect("never cancelled").0
+    }
+
+    #[test]
+    fn charts_are_identical_across_runs_and_thread_counts() {
+        let image = test_image();
+        let mut config = Config::default();
+        config.seed = 42;
+
+        let first = chart_fingerprint(&analyze(&mut Pipeline::default(), &image, &config));
+        assert!(chart_fingerprint(&analyze(&mut Pipeline::default(), &image, &config)) == first);
+        for threads in [1, 2, 5] {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().expect("thread pool should build");
+            let chart = pool.install(|| analyze(&mut Pipeline::default(), &image, &config));
+            assert!(chart_fingerprint(&chart) == first, "{} threads gave a different chart", threads);
         }
     }
+
+    /// How many times each stage has run, from preprocessing to floss mapping.
+    fn runs(pipeline: &Pipeline) -> [usize; 5] {
+        [pipeline.preprocessed.runs, pipeline.resampled.runs, pipeline.quantized.runs, pipeline.cleaned.runs, pipeline.mapped.runs]
+    }
+
+    #[test]
+    fn stages_run_once_for_the_same_inputs() {
+        let mut stage = Stage::new();
+        assert_eq!(stage.get_or_run(1, || Some("one")), Some(&"one"));
+        assert_eq!(stage.get_or_run(1, || Some("again")), Some(&"one"));
+        assert_eq!(stage.runs, 1);
+
+        assert_eq!(stage.get_or_run(2, || Some("two")), Some(&"two"));
+        assert_eq!(stage.runs, 2);
+    }
+
+    #[test]
+    fn cancelled_stages_cache_nothing() {
+        let mut stage = Stage::new();
+        assert_eq!(stage.get_or_run(1, || Some("one")), Some(&"one"));
+        assert_eq!(stage.get_or_run(2, || None), None);
+        assert_eq!(stage.get_or_run(1, || Some("rerun")), Some(&"rerun"));
+    }
+
+    #[test]
+    fn later_settings_reuse_earlier_stages() {
+        let image = test_image();
+        let mut config = Config::default();
+        let mut pipeline = Pipeline::default();
+        analyze(&mut pipeline, &image, &config);
+        assert_eq!(runs(&pipeline), [1, 1, 1, 1, 1]);
+
+        // The same settings again run nothing.
+        analyze(&mut pipeline, &image, &config);
+        assert_eq!(runs(&pipeline), [1, 1, 1, 1, 1]);
+
+        // Cleanup only feeds the chart, so only it runs again.
+        config.confetti_neighbors = 2;
+        analyze(&mut pipeline, &image, &config);
+        assert_eq!(runs(&pipeline), [1, 1, 1, 2, 1]);
+
+        // Floss mapping sits beside cleanup, after quantizing.
+        config.use_blends = true;
+        analyze(&mut pipeline, &image, &config);
+        assert_eq!(runs(&pipeline), [1, 1, 1, 2, 2]);
+    }
+
+    #[test]
+    fn earlier_settings_invalidate_later_stages() {
+        let image = test_image();
+        let mut config = Config::default();
+        let mut pipeline = Pipeline::default();
+        analyze(&mut pipeline, &image, &config);
+
+        config.num_colors = 12;
+        analyze(&mut pipeline, &image, &config);
+        assert_eq!(runs(&pipeline), [1, 1, 2, 2, 2]);
+
+        config.num_width = 30;
+        analyze(&mut pipeline, &image, &config);
+        assert_eq!(runs(&pipeline), [1, 2, 3, 3, 3]);
+
+        // A different image starts over from preprocessing.
+        pipeline.update_pattern(&image, 2, &config, None, None, &Cancellation::never());
+        assert_eq!(runs(&pipeline), [2, 3, 4, 4, 4]);
+    }
 }
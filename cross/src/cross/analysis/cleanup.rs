@@ -0,0 +1,51 @@
+//! Removes isolated "confetti" stitches, which take a lot of thread changes for very little detail
+use rayon::prelude::*;
+
+/// Replaces every stitch with fewer than `min_neighbors` matching neighbors (of the surrounding 8)
+/// with the most common color around it. Zero disables cleanup.
+///
+/// Every stitch is judged against the original grid, so the result doesn't depend on scan order.
+/// Protected stitches are always kept, as small details there are intentional.
+pub fn remove_confetti(stitches: &[usize], protected: &[bool], width: usize, height: usize, min_neighbors: usize) -> Vec<usize> {
+    if min_neighbors == 0 {
+        return stitches.to_vec();
+    }
+
+    (0..height).into_par_iter().flat_map_iter(|y| {
+        (0..width).map(move |x| {
+            let color = stitches[x + y * width];
+            if protected[x + y * width] {
+                return color;
+            }
+
+            let neighbors = neighbors(x, y, width, height).map(|(nx, ny)| stitches[nx + ny * width]);
+
+            let mut counts: Vec<(usize, usize)> = Vec::with_capacity(8);
+            for neighbor in neighbors {
+                match counts.iter_mut().find(|(c, _)| *c == neighbor) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((neighbor, 1)),
+                }
+            }
+
+            let matching = counts.iter().find(|(c, _)| *c == color).map_or(0, |(_, count)| *count);
+            if matching >= min_neighbors {
+                return color;
+            }
+
+            // Ties go to the lowest palette index, to stay deterministic.
+            counts.iter()
+                .max_by(|(a_color, a_count), (b_color, b_count)| a_count.cmp(b_count).then(b_color.cmp(a_color)))
+                .map_or(color, |(c, _)| *c)
+        })
+    }).collect()
+}
+
+/// The up to 8 stitches surrounding (x, y)
+pub fn neighbors(x: usize, y: usize, width: usize, height: usize) -> impl Iterator<Item = (usize, usize)> {
+    (-1i32..=1).flat_map(move |dy| (-1i32..=1).map(move |dx| (dx, dy)))
+        .filter(|&(dx, dy)| dx != 0 || dy != 0)
+        .map(move |(dx, dy)| (x as i32 + dx, y as i32 + dy))
+        .filter(move |&(nx, ny)| nx >= 0 && ny >= 0 && (nx as usize) < width && (ny as usize) < height)
+        .map(|(nx, ny)| (nx as usize, ny as usize))
+}
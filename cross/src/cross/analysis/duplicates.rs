@@ -0,0 +1,44 @@
+//! Finds palette colors whose threads look the same once stitched, which k-means happily keeps apart
+use crate::cross::color::Lab;
+use crate::cross::renderer::ChartData;
+
+/// Two palette colors whose threads are closer than the threshold
+pub struct DuplicatePair {
+    pub first: usize,
+    pub second: usize,
+    pub delta_e: f32,
+}
+
+/// Pairs of used colors whose threads are within `max_delta_e` of each other, closest first.
+pub fn near_duplicates(chart_data: &ChartData, max_delta_e: f32) -> Vec<DuplicatePair> {
+    let labs: Vec<Lab> = chart_data.palette.iter().map(|color| Lab::from(color.thread_color())).collect();
+    let counts = chart_data.color_counts();
+    let used: Vec<usize> = (0..counts.len()).filter(|i| counts[*i] > 0).collect();
+
+    let mut pairs = vec![];
+    for (i, first) in used.iter().enumerate() {
+        for second in &used[i + 1..] {
+            let delta_e = labs[*first].delta_e(&labs[*second]);
+            if delta_e < max_delta_e {
+                pairs.push(DuplicatePair { first: *first, second: *second, delta_e });
+            }
+        }
+    }
+
+    pairs.sort_by(|a, b| a.delta_e.total_cmp(&b.delta_e));
+    pairs
+}
+
+/// Merges the closest near-duplicate pair, then looks again, until no pair is within `max_delta_e`.
+/// The less-used color of a pair takes the other's thread.
+pub fn merge_near_duplicates(chart_data: &mut ChartData, max_delta_e: f32) {
+    while let Some(pair) = near_duplicates(chart_data, max_delta_e).into_iter().next() {
+        let counts = chart_data.color_counts();
+        let (kept, removed) = if counts[pair.second] > counts[pair.first] { (pair.second, pair.first) } else { (pair.first, pair.second) };
+        for stitch in chart_data.stitches.iter_mut().filter(|stitch| **stitch == removed) {
+            *stitch = kept;
+        }
+
+        chart_data.remove_unused_colors();
+    }
+}
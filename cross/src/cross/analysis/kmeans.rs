@@ -0,0 +1,182 @@
+//! Seeded k-means clustering of stitch colors.
+//! Work is spread across rayon's thread pool, but every result is combined in a fixed order,
+//! so the same colors and seed always give bit-identical clusters no matter how many cores run it.
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+
+use super::Cancellation;
+
+// Color sums are accumulated per fixed-size chunk and then added up in chunk order,
+// because floating point addition depends on order and rayon's splitting does not.
+const CHUNK_SIZE: usize = 4096;
+
+pub struct Clusters {
+    pub centroids: Vec<[f32; 3]>,
+    pub membership: Vec<usize>,
+
+    // True if the centroids are the image's own colors rather than averages
+    pub exact: bool,
+}
+
+fn dist_sqd(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    let d0 = a[0] - b[0];
+    let d1 = a[1] - b[1];
+    let d2 = a[2] - b[2];
+    d0 * d0 + d1 * d1 + d2 * d2
+}
+
+/// Index of the closest centroid. Ties go to the lowest index.
+fn nearest(color: &[f32; 3], centroids: &[[f32; 3]]) -> usize {
+    let mut best = 0;
+    let mut best_distance = f32::MAX;
+    for (i, centroid) in centroids.iter().enumerate() {
+        let distance = dist_sqd(color, centroid);
+        if distance < best_distance {
+            best = i;
+            best_distance = distance;
+        }
+    }
+
+    best
+}
+
+/// Returns None if cancelled. May return fewer than k clusters if there are fewer distinct colors.
+/// Each color pulls its centroid (and k-means++ seeding) in proportion to its weight.
+pub fn kmeans(colors: &[[f32; 3]], weights: &[f32], k: usize, max_iterations: usize, seed: u64, cancellation: &Cancellation) -> Option<Clusters> {
+    if colors.is_empty() || k == 0 {
+        return Some(Clusters { centroids: vec![], membership: vec![], exact: false });
+    }
+
+    let mut centroids = initial_centroids(colors, weights, k, seed);
+    let mut membership = vec![usize::MAX; colors.len()];
+
+    for _ in 0..max_iterations {
+        // Early-exit if a newer job is waiting.
+        if cancellation.is_cancelled() {
+            return None;
+        }
+
+        let new_membership: Vec<usize> = colors.par_iter().map(|c| nearest(c, &centroids)).collect();
+        let converged = new_membership == membership;
+        membership = new_membership;
+        if converged {
+            break;
+        }
+
+        let partial_sums: Vec<Vec<([f64; 3], f64)>> = colors.par_chunks(CHUNK_SIZE)
+            .zip(weights.par_chunks(CHUNK_SIZE))
+            .zip(membership.par_chunks(CHUNK_SIZE))
+            .map(|((chunk_colors, chunk_weights), chunk_membership)| {
+                let mut sums = vec![([0.0; 3], 0.0); centroids.len()];
+                for ((c, weight), cluster) in chunk_colors.iter().zip(chunk_weights).zip(chunk_membership) {
+                    let (sum, total_weight) = &mut sums[*cluster];
+                    let weight = *weight as f64;
+                    sum[0] += c[0] as f64 * weight;
+                    sum[1] += c[1] as f64 * weight;
+                    sum[2] += c[2] as f64 * weight;
+                    *total_weight += weight;
+                }
+                sums
+            })
+            .collect();
+
+        let mut totals = vec![([0.0; 3], 0.0); centroids.len()];
+        for sums in partial_sums {
+            for ((total, total_weight), (sum, weight)) in totals.iter_mut().zip(sums) {
+                total[0] += sum[0];
+                total[1] += sum[1];
+                total[2] += sum[2];
+                *total_weight += weight;
+            }
+        }
+
+        // Empty clusters keep their previous centroid.
+        for (centroid, (sum, weight)) in centroids.iter_mut().zip(totals) {
+            if weight > 0.0 {
+                *centroid = [
+                    (sum[0] / weight) as f32,
+                    (sum[1] / weight) as f32,
+                    (sum[2] / weight) as f32];
+            }
+        }
+    }
+
+    Some(Clusters { centroids, membership, exact: false })
+}
+
+// k-means++: each new centroid is picked with probability proportional to its (weighted) squared distance
+// from the closest existing centroid, which spreads the starting colors out across the image.
+// https://en.wikipedia.org/wiki/K-means%2B%2B
+fn initial_centroids(colors: &[[f32; 3]], weights: &[f32], k: usize, seed: u64) -> Vec<[f32; 3]> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut centroids = vec![colors[rng.gen_range(0..colors.len())]];
+    let mut distances: Vec<f32> = colors.par_iter().map(|c| dist_sqd(c, &centroids[0])).collect();
+
+    while centroids.len() < k {
+        let total: f64 = distances.iter().zip(weights).map(|(d, w)| (*d * *w) as f64).sum();
+        if total == 0.0 {
+            // Every color already has an exact centroid
+            break;
+        }
+
+        let target = rng.gen::<f64>() * total;
+        let mut accumulated = 0.0;
+        let mut chosen = colors.len() - 1;
+        for (i, (distance, weight)) in distances.iter().zip(weights).enumerate() {
+            accumulated += (*distance * *weight) as f64;
+            if accumulated > target {
+                chosen = i;
+                break;
+            }
+        }
+
+        let centroid = colors[chosen];
+        centroids.push(centroid);
+        distances.par_iter_mut().zip(colors.par_iter()).for_each(|(distance, c)| {
+            *distance = distance.min(dist_sqd(c, &centroid));
+        });
+    }
+
+    centroids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Noisy colors around a few hues, enough of them to span several chunks.
+    fn test_colors() -> (Vec<[f32; 3]>, Vec<f32>) {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let hues = [[0.9, 0.1, 0.1], [0.1, 0.6, 0.2], [0.2, 0.2, 0.8], [0.9, 0.9, 0.8], [0.1, 0.1, 0.1]];
+        let colors: Vec<[f32; 3]> = (0..3 * CHUNK_SIZE + 123).map(|i| {
+            let hue = hues[i % hues.len()];
+            [hue[0] + rng.gen_range(-0.1..0.1), hue[1] + rng.gen_range(-0.1..0.1), hue[2] + rng.gen_range(-0.1..0.1)]
+        }).collect();
+        let weights = (0..colors.len()).map(|i| if i % 17 == 0 { 5.0 } else { 1.0 }).collect();
+        (colors, weights)
+    }
+
+    fn run(colors: &[[f32; 3]], weights: &[f32], seed: u64, threads: usize) -> (Vec<[u32; 3]>, Vec<usize>) {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().expect("thread pool should build");
+        let clusters = pool.install(|| kmeans(colors, weights, 8, 50, seed, &Cancellation::never())).expect("never cancelled");
+        let centroids = clusters.centroids.iter().map(|c| [c[0].to_bits(), c[1].to_bits(), c[2].to_bits()]).collect();
+        (centroids, clusters.membership)
+    }
+
+    #[test]
+    fn same_seed_gives_identical_clusters() {
+        let (colors, weights) = test_colors();
+        assert!(run(&colors, &weights, 42, 4) == run(&colors, &weights, 42, 4));
+    }
+
+    #[test]
+    fn thread_count_does_not_change_clusters() {
+        let (colors, weights) = test_colors();
+        let single = run(&colors, &weights, 42, 1);
+        assert_eq!(single.0.len(), 8);
+        for threads in [2, 3, 8] {
+            assert!(run(&colors, &weights, 42, threads) == single, "{} threads gave different clusters", threads);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Objective measures of how well a chart matches its image and how pleasant it is to stitch
+use crate::egui::{Color32, ColorImage};
+
+use rayon::prelude::*;
+
+use super::cleanup;
+use super::ColorPoint;
+use crate::cross::color::Lab;
+use crate::cross::renderer::ChartData;
+
+// Errors at or above this ΔE are drawn fully red on the heatmap.
+const HEATMAP_MAX_ERROR: f32 = 20.0;
+
+pub struct Metrics {
+    // ΔE between each stitch's thread and the resampled image, row-major
+    pub errors: Vec<f32>,
+    pub mean_error: f32,
+    pub max_error: f32,
+
+    // Average number of times the color changes going along a row
+    pub color_changes_per_row: f32,
+
+    // Stitches with none of their 8 neighbors the same color
+    pub isolated_stitches: usize,
+}
+
+/// Compares the chart against the resampled image it was made from, one point per stitch.
+pub fn measure(chart_data: &ChartData, source: &[ColorPoint]) -> Metrics {
+    let thread_labs: Vec<Lab> = chart_data.palette.iter().map(|color| Lab::from(color.thread_color())).collect();
+    let errors: Vec<f32> = chart_data.stitches.par_iter().zip(source).map(|(palette_index, point)| {
+        Lab::from(point.c).delta_e(&thread_labs[*palette_index])
+    }).collect();
+
+    let (width, height) = (chart_data.width, chart_data.height);
+    let color_changes: usize = chart_data.stitches.chunks(width.max(1))
+        .map(|row| row.windows(2).filter(|pair| pair[0] != pair[1]).count())
+        .sum();
+
+    let isolated_stitches = (0..height).into_par_iter().map(|y| {
+        (0..width).filter(|&x| {
+            let color = chart_data.stitches[x + y * width];
+            cleanup::neighbors(x, y, width, height).all(|(nx, ny)| chart_data.stitches[nx + ny * width] != color)
+        }).count()
+    }).sum();
+
+    Metrics {
+        mean_error: errors.iter().sum::<f32>() / errors.len().max(1) as f32,
+        max_error: errors.iter().copied().fold(0.0, f32::max),
+        errors,
+        color_changes_per_row: color_changes as f32 / height.max(1) as f32,
+        isolated_stitches,
+    }
+}
+
+impl Metrics {
+    /// Tints each stitch from faint yellow (close match) to solid red (poor match), for drawing over the chart.
+    pub fn heatmap(&self, chart_data: &ChartData) -> ColorImage {
+        ColorImage {
+            size: [chart_data.width, chart_data.height],
+            pixels: self.errors.iter().map(|error| {
+                let t = (error / HEATMAP_MAX_ERROR).min(1.0);
+                Color32::from_rgba_unmultiplied(255, (255.0 * (1.0 - t)) as u8, 0, (60.0 + 160.0 * t) as u8)
+            }).collect(),
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Charts pixel art exactly, one stitch per art pixel, instead of resampling and re-clustering it
+use crate::egui::ColorImage;
+
+use std::collections::{HashMap, HashSet};
+
+use super::kmeans::Clusters;
+use super::ColorPoint;
+
+// Pixel art at its native size has a small palette, with most colors running on for a few pixels.
+const NATIVE_MAX_COLORS: usize = 64;
+const NATIVE_MIN_RUN: f32 = 2.0;
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Greatest common divisor of the lengths of same-colored runs along each line of pixels.
+/// Pixel art scaled up by a whole factor only has runs that are multiples of that factor.
+fn run_scale(lines: impl Iterator<Item = Vec<[u8; 4]>>) -> usize {
+    let mut scale = 0;
+    for line in lines {
+        let mut run = 1;
+        for pair in line.windows(2) {
+            if pair[0] == pair[1] {
+                run += 1;
+            } else {
+                scale = gcd(scale, run);
+                run = 1;
+            }
+        }
+        scale = gcd(scale, run);
+
+        if scale == 1 {
+            break;
+        }
+    }
+
+    scale.max(1)
+}
+
+/// Whether the image has at most `max_colors` distinct colors, in runs averaging at least `min_run` pixels along its rows.
+fn few_colors_in_runs(image: &ColorImage, max_colors: usize, min_run: f32) -> bool {
+    let mut colors = HashSet::new();
+    let mut runs = 0;
+    for (i, pixel) in image.pixels.iter().enumerate() {
+        if i % image.size[0] == 0 || image.pixels[i - 1] != *pixel {
+            runs += 1;
+        }
+        if colors.insert(pixel.to_array()) && colors.len() > max_colors {
+            return false;
+        }
+    }
+
+    image.pixels.len() as f32 >= min_run * runs as f32
+}
+
+/// Size in art pixels, if the image looks like pixel art: scaled up by a whole factor in either direction,
+/// or at its native size with few colors in runs of several pixels. None for photos and other images,
+/// whose run lengths share no factor.
+pub fn native_grid(image: &ColorImage) -> Option<[usize; 2]> {
+    let [width, height] = image.size;
+    let pixel = |x: usize, y: usize| image.pixels[x + y * width].to_array();
+    let scale_x = run_scale((0..height).map(|y| (0..width).map(|x| pixel(x, y)).collect()));
+    let scale_y = run_scale((0..width).map(|x| (0..height).map(|y| pixel(x, y)).collect()));
+    let scaled = scale_x > 1 || scale_y > 1;
+    (scaled || few_colors_in_runs(image, NATIVE_MAX_COLORS, NATIVE_MIN_RUN)).then_some([width / scale_x, height / scale_y])
+}
+
+/// Uses the points' own colors as the palette if there are at most `max_colors` of them.
+/// Colors are numbered in the order they first appear.
+pub fn exact_palette(points: &[ColorPoint], max_colors: usize) -> Option<Clusters> {
+    let mut indices: HashMap<[u32; 3], usize> = HashMap::new();
+    let mut centroids = vec![];
+    let mut membership = Vec::with_capacity(points.len());
+    for point in points {
+        let color = [point.c.r(), point.c.g(), point.c.b()];
+        let index = *indices.entry(color.map(f32::to_bits)).or_insert_with(|| {
+            centroids.push(color);
+            centroids.len() - 1
+        });
+        if centroids.len() > max_colors {
+            return None;
+        }
+
+        membership.push(index);
+    }
+
+    Some(Clusters { centroids, membership, exact: true })
+}
@@ -0,0 +1,59 @@
+//! Drops colors used for too few stitches to be worth buying a skein for
+use crate::cross::color::Lab;
+use crate::cross::renderer::{ChartData, PaletteColor};
+
+/// A color that was pruned, and the color its stitches went to.
+pub struct PrunedColor {
+    pub color: PaletteColor,
+    pub stitches: usize,
+    pub merged_into: PaletteColor,
+}
+
+/// Removes the least-used color below `min_stitches` and gives its stitches to the perceptually closest remaining color,
+/// one color at a time, until every color left has at least `min_stitches` stitches (or only one color is left).
+/// Merged stitches count towards their new color, so a color just short of the minimum may be saved by a merge.
+/// Colors with any `protected` (masked) stitch are kept however few stitches they have, though others may merge into them.
+/// Zero disables pruning. Returns the pruned colors in the order they were removed.
+pub fn prune(chart_data: &mut ChartData, protected: &[bool], min_stitches: usize) -> Vec<PrunedColor> {
+    let labs: Vec<Lab> = chart_data.palette.iter().map(|color| Lab::from(color.thread_color())).collect();
+    let mut counts = chart_data.color_counts();
+    let mut kept = vec![false; counts.len()];
+    for (stitch, _) in protected.iter().enumerate().filter(|(_, protected)| **protected) {
+        kept[chart_data.stitches[stitch]] = true;
+    }
+
+    let mut pruned = vec![];
+    loop {
+        let used: Vec<usize> = (0..counts.len()).filter(|i| counts[*i] > 0).collect();
+        if used.len() <= 1 {
+            break;
+        }
+
+        // Ties go to the lowest palette index, to stay deterministic.
+        let Some(fewest) = used.iter().copied().filter(|i| counts[*i] < min_stitches && !kept[*i]).min_by_key(|i| counts[*i]) else {
+            break;
+        };
+        let closest = used.iter().copied()
+            .filter(|i| *i != fewest)
+            .min_by(|a, b| labs[fewest].delta_e(&labs[*a]).total_cmp(&labs[fewest].delta_e(&labs[*b])))
+            .expect("another color is always left");
+
+        for stitch in chart_data.stitches.iter_mut().filter(|stitch| **stitch == fewest) {
+            *stitch = closest;
+        }
+        pruned.push(PrunedColor {
+            color: chart_data.palette[fewest].clone(),
+            stitches: counts[fewest],
+            merged_into: chart_data.palette[closest].clone(),
+        });
+        counts[closest] += counts[fewest];
+        counts[fewest] = 0;
+    }
+
+    // Pruned colors leave the palette entirely, so they don't turn up in the legend or exports.
+    if !pruned.is_empty() {
+        chart_data.remove_unused_colors();
+    }
+
+    pruned
+}
@@ -0,0 +1,87 @@
+//! Suggests how many colors an image needs, from how quantization error falls as colors are added
+use super::{limit_colors, map_to_floss, metrics, Cancellation, ColorPoint, Threads};
+use crate::cross::config::Config;
+use crate::cross::floss::Floss;
+use crate::cross::renderer::ChartData;
+
+// The sweep runs k-means many times, so it stops short of full convergence.
+// The error at each count only needs to be good enough to compare against its neighbors.
+const SWEEP_MAX_ITERATIONS: i32 = 20;
+
+/// Mean color error (ΔE) of the chart at each color count tried
+pub struct ColorCurve {
+    pub points: Vec<(i32, f32)>,
+}
+
+pub struct Suggestion {
+    pub num_colors: i32,
+
+    // False if no count got down to the target error, and the elbow of the curve was picked instead.
+    pub reached_target: bool,
+}
+
+/// Every count up to 10, then coarser steps, as the curve flattens out with more colors.
+fn sweep_counts() -> impl Iterator<Item = i32> {
+    (2..=10).chain((12..=30).step_by(2)).chain((35..=50).step_by(5))
+}
+
+/// Quantizes and floss-maps the resampled image at a range of color counts, with the rest of the settings as given.
+/// Returns None if cancelled.
+pub fn sweep(points: &[ColorPoint], config: &Config, stash: Option<&[&'static Floss]>, protected: &[bool], cancellation: &Cancellation) -> Option<ColorCurve> {
+    // Matching threads doesn't depend on the color count, and there are a lot of them with blends.
+    let threads = Threads::new(config.craft.catalog(), stash, super::use_blends(config));
+    let mut curve = ColorCurve { points: vec![] };
+    for num_colors in sweep_counts() {
+        let mut sweep_config = config.clone();
+        sweep_config.num_colors = num_colors;
+        sweep_config.num_iterations = config.num_iterations.min(SWEEP_MAX_ITERATIONS);
+
+        let clusters = limit_colors(&sweep_config, points, protected, cancellation)?;
+        let mapping = map_to_floss(&clusters.centroids, &threads, clusters.exact);
+        let chart_data = ChartData {
+            width: config.num_width as usize,
+            height: config.num_height as usize,
+            geometry: config.craft.geometry(config),
+            catalog: config.craft.catalog(),
+            palette: mapping.palette,
+            stitches: clusters.membership.iter().map(|cluster| mapping.cluster_colors[*cluster]).collect(),
+            backstitches: vec![],
+        };
+        curve.points.push((num_colors, metrics::measure(&chart_data, points).mean_error));
+    }
+
+    Some(curve)
+}
+
+impl ColorCurve {
+    /// The fewest colors that reach `target_error`, or failing that, the elbow where adding colors stops paying off.
+    pub fn suggest(&self, target_error: f32) -> Suggestion {
+        if let Some((num_colors, _)) = self.points.iter().find(|(_, error)| *error <= target_error) {
+            return Suggestion { num_colors: *num_colors, reached_target: true };
+        }
+
+        Suggestion { num_colors: self.elbow(), reached_target: false }
+    }
+
+    /// The point furthest from the straight line between the curve's ends, with both axes scaled to 0-1.
+    fn elbow(&self) -> i32 {
+        let (Some(first), Some(last)) = (self.points.first(), self.points.last()) else {
+            return 0;
+        };
+
+        let x_range = (last.0 - first.0).max(1) as f32;
+        let y_range = (first.1 - last.1).abs().max(f32::EPSILON);
+        let normalize = |(count, error): (i32, f32)| ((count - first.0) as f32 / x_range, (error - last.1) / y_range);
+
+        // The chord runs from (0, first error) to (1, 0). Distances are compared, so the constant scale is dropped.
+        let (_, first_y) = normalize(*first);
+        let distance = |point: (i32, f32)| {
+            let (x, y) = normalize(point);
+            (first_y * x + y - first_y).abs()
+        };
+
+        self.points.iter()
+            .max_by(|a, b| distance(**a).total_cmp(&distance(**b)))
+            .map_or(first.0, |(num_colors, _)| *num_colors)
+    }
+}
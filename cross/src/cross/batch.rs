@@ -0,0 +1,237 @@
+//! Converts a whole folder of images with the same settings, in parallel, off the UI thread
+use rayon::prelude::*;
+
+use std::fmt::Write;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use super::analysis::{Cancellation, Metrics, Pipeline};
+use super::config::Config;
+use super::data;
+use super::decorations::Decorations;
+use super::embroidery::StitchPlan;
+use super::floss::Floss;
+use super::input;
+use super::mask::Mask;
+use super::progress::Progress;
+use super::project::Project;
+use super::renderer::ChartData;
+use super::schedule::{self, Schedule};
+use super::svg;
+
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png"];
+
+// The report of every image in the batch is saved as this, with a .csv extension.
+const REPORT_STEM: &str = "summary";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Export {
+    Project,
+    ChartImage,
+    Svg,
+    Data,
+    Checklist,
+    Dst,
+    Pes,
+}
+
+impl Export {
+    pub const ALL: [Export; 7] = [Export::Project, Export::ChartImage, Export::Svg, Export::Data, Export::Checklist, Export::Dst, Export::Pes];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Export::Project => "Project",
+            Export::ChartImage => "Chart image",
+            Export::Svg => "SVG chart",
+            Export::Data => "JSON and CSV data",
+            Export::Checklist => "Schedule checklist",
+            Export::Dst => "Tajima DST",
+            Export::Pes => "Brother PES",
+        }
+    }
+
+    fn file_name(self, stem: &str) -> String {
+        match self {
+            Export::Project => format!("{}.json", stem),
+            Export::ChartImage => format!("{}.png", stem),
+            Export::Svg => format!("{}.svg", stem),
+            Export::Data => format!("{}-data.json", stem),
+            Export::Checklist => format!("{}-schedule.md", stem),
+            Export::Dst => format!("{}.dst", stem),
+            Export::Pes => format!("{}.pes", stem),
+        }
+    }
+
+    fn write(self, path: &Path, image_path: &Path, config: &Config, chart_data: &ChartData, schedule: &Schedule) -> io::Result<()> {
+        let name = path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().to_string());
+        match self {
+            Export::Project => {
+                let mut progress = Progress::default();
+                progress.fit(chart_data);
+                Project::new(Some(image_path.to_path_buf()), config, chart_data, &progress, &Mask::default(), &Decorations::default(), &[]).save(path)
+            },
+            Export::ChartImage => chart_data.save_image(path),
+            Export::Svg => std::fs::write(path, svg::to_svg(chart_data)),
+            Export::Data => data::save(path, chart_data, config),
+            Export::Checklist => std::fs::write(path, schedule.to_checklist(chart_data)),
+            Export::Dst => std::fs::write(path, StitchPlan::new(chart_data, config.craft.cell_mm(config)).to_dst(&name)),
+            Export::Pes => std::fs::write(path, StitchPlan::new(chart_data, config.craft.cell_mm(config)).to_pes(&name)),
+        }
+    }
+}
+
+/// Base names for each image's exported files, in the same order.
+/// Images sharing a name, such as `a.jpg` and `a.png` or the same name in different folders, all keep their extension
+/// (so the chart image of one can't overwrite the other), then get a number if that's still not enough.
+/// The summary report's name is kept free too.
+fn output_stems(images: &[PathBuf]) -> Vec<String> {
+    // File names may not be case-sensitive, so neither are the comparisons.
+    let stem_of = |path: &PathBuf| path.file_stem().map_or_else(|| "pattern".to_string(), |stem| stem.to_string_lossy().to_string());
+    let stems: Vec<String> = images.iter().map(stem_of).collect();
+    let shared = |stem: &str| stem.eq_ignore_ascii_case(REPORT_STEM) || stems.iter().filter(|other| other.eq_ignore_ascii_case(stem)).count() > 1;
+
+    let mut taken: Vec<String> = vec![];
+    images.iter().zip(&stems).map(|(path, stem)| {
+        let base = match path.extension() {
+            Some(extension) if shared(stem) => format!("{}-{}", stem, extension.to_string_lossy()),
+            _ => stem.clone(),
+        };
+        let unique = std::iter::once(base.clone())
+            .chain((2..).map(|n| format!("{}-{}", base, n)))
+            .find(|name| !name.eq_ignore_ascii_case(REPORT_STEM) && !taken.iter().any(|taken| taken.eq_ignore_ascii_case(name)))
+            .expect("numbered names never run out");
+        taken.push(unique.clone());
+        unique
+    }).collect()
+}
+
+/// Images in a folder, or matching a glob pattern such as `photos/*.jpg`.
+pub fn find_images(input: &str) -> io::Result<Vec<PathBuf>> {
+    let is_image = |path: &Path| path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| IMAGE_EXTENSIONS.contains(&extension.to_lowercase().as_str()));
+
+    let mut images: Vec<PathBuf> = if Path::new(input).is_dir() {
+        std::fs::read_dir(input)?.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect()
+    } else {
+        glob::glob(input)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?
+            .filter_map(Result::ok)
+            .collect()
+    };
+
+    images.retain(|path| path.is_file() && is_image(path));
+    images.sort();
+    Ok(images)
+}
+
+/// What came of one image
+pub struct Summary {
+    pub width: usize,
+    pub height: usize,
+    pub colors: usize,
+    pub days: usize,
+    pub metrics: Metrics,
+}
+
+pub struct ImageResult {
+    pub path: PathBuf,
+    pub summary: Result<Summary, String>,
+}
+
+fn process(path: &Path, stem: &str, config: &Config, stash: Option<&[&'static Floss]>, output_dir: &Path, exports: &[Export]) -> Result<Summary, String> {
+    let image = input::load_image_from_path(path).map_err(|err| format!("unable to load image: {}", err))?;
+
+    // Pixel art is charted at its own size, as it is in the app, and the days follow the size.
+    let mut config = config.clone();
+    if config.pixel_art {
+        if let Some([width, height]) = super::pixel_art_grid(&image) {
+            config.set_size(width as i32, height as i32);
+        }
+    }
+
+    let (chart_data, metrics, _) = Pipeline::default()
+        .update_pattern(&image, 1, &config, stash, None, &Cancellation::never())
+        .ok_or_else(|| "image is empty".to_string())?;
+    let schedule = schedule::plan(&chart_data, config.num_days);
+
+    for export in exports {
+        export.write(&output_dir.join(export.file_name(stem)), path, &config, &chart_data, &schedule)
+            .map_err(|err| format!("unable to save {}: {}", export.name().to_lowercase(), err))?;
+    }
+
+    Ok(Summary {
+        width: chart_data.width,
+        height: chart_data.height,
+        colors: chart_data.color_counts().iter().filter(|count| **count > 0).count(),
+        days: schedule.days.len(),
+        metrics,
+    })
+}
+
+/// One row per image, with failures listed alongside the rest.
+fn write_report(path: &Path, results: &[ImageResult]) -> io::Result<()> {
+    let mut csv = String::from("image,width,height,colors,days,mean_error,max_error,color_changes_per_row,isolated_stitches,error\n");
+    for result in results {
+        let name = result.path.file_name().map_or_else(String::new, |name| name.to_string_lossy().replace(',', "_"));
+        _ = match &result.summary {
+            Ok(summary) => writeln!(csv, "{},{},{},{},{},{:.2},{:.2},{:.2},{},",
+                name, summary.width, summary.height, summary.colors, summary.days,
+                summary.metrics.mean_error, summary.metrics.max_error, summary.metrics.color_changes_per_row, summary.metrics.isolated_stitches),
+            Err(err) => writeln!(csv, "{},,,,,,,,,{}", name, err.replace(',', ";")),
+        };
+    }
+
+    std::fs::write(path, csv)
+}
+
+/// A batch running in the background.
+pub struct Batch {
+    pub total: usize,
+    finished: Arc<AtomicUsize>,
+    handle: Option<JoinHandle<Vec<ImageResult>>>,
+    pub report_path: PathBuf,
+}
+
+impl Batch {
+    pub fn start(images: Vec<PathBuf>, config: Config, stash: Option<Vec<&'static Floss>>, output_dir: PathBuf, exports: Vec<Export>) -> Self {
+        let total = images.len();
+        let finished = Arc::new(AtomicUsize::new(0));
+        let report_path = output_dir.join(format!("{}.csv", REPORT_STEM));
+        let stems = output_stems(&images);
+
+        let thread_finished = finished.clone();
+        let thread_report_path = report_path.clone();
+        let handle = thread::spawn(move || {
+            let results: Vec<ImageResult> = images.par_iter().zip(&stems).map(|(path, stem)| {
+                let summary = process(path, stem, &config, stash.as_deref(), &output_dir, &exports);
+                thread_finished.fetch_add(1, Ordering::Relaxed);
+                ImageResult { path: path.clone(), summary }
+            }).collect();
+
+            if let Err(err) = write_report(&thread_report_path, &results) {
+                print!("Unable to save batch summary: {}", err);
+            }
+            results
+        });
+
+        Batch { total, finished, handle: Some(handle), report_path }
+    }
+
+    pub fn finished(&self) -> usize {
+        self.finished.load(Ordering::Relaxed)
+    }
+
+    /// The results once every image is done, or an error if the batch stopped part way.
+    pub fn poll(&mut self) -> Option<Result<Vec<ImageResult>, String>> {
+        if !self.handle.as_ref().is_some_and(JoinHandle::is_finished) {
+            return None;
+        }
+
+        let handle = self.handle.take()?;
+        Some(handle.join().map_err(|_| "the batch stopped unexpectedly".to_string()))
+    }
+}
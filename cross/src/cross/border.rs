@@ -0,0 +1,144 @@
+//! Decorative frames around the chart, built from repeating motifs that turn the corners on their own
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+use super::decorations;
+use super::floss::Catalog;
+use super::renderer::ChartData;
+
+// Margins the app offers, in stitches
+pub const MARGIN_RANGE: RangeInclusive<usize> = 0..=20;
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Motif {
+    SingleLine,
+    DoubleLine,
+    GreekKey,
+    Vines,
+    Checkerboard,
+}
+
+impl Motif {
+    pub const ALL: [Motif; 5] = [Motif::SingleLine, Motif::DoubleLine, Motif::GreekKey, Motif::Vines, Motif::Checkerboard];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Motif::SingleLine => "Single line",
+            Motif::DoubleLine => "Double line",
+            Motif::GreekKey => "Greek key",
+            Motif::Vines => "Vines",
+            Motif::Checkerboard => "Checkerboard",
+        }
+    }
+
+    /// One repeat of the motif as it runs along the top edge, from the outside in.
+    /// Each row is one stitch deep, with '#' stitched in the border floss.
+    fn rows(self) -> &'static [&'static str] {
+        match self {
+            Motif::SingleLine => &["#"],
+            Motif::DoubleLine => &["#", ".", "#"],
+            Motif::GreekKey => &[
+                "#####.",
+                "#...#.",
+                "#.###.",
+                "#.#...",
+                "#.####",
+            ],
+            Motif::Vines => &[
+                "....#.",
+                ".##.#.",
+                "#..#..",
+                ".#..##",
+                ".#....",
+            ],
+            Motif::Checkerboard => &["#.", ".#", "#."],
+        }
+    }
+
+    pub fn thickness(self) -> usize {
+        self.rows().len()
+    }
+
+    fn stitched(self, along: usize, depth: usize) -> bool {
+        let row = self.rows()[depth].as_bytes();
+        row[along % row.len()] == b'#'
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Border {
+    pub motif: Motif,
+
+    // Catalog codes of the motif, and of the margin and the gaps in the motif
+    pub floss: String,
+    pub background: String,
+
+    // Background stitches between the chart and the motif
+    pub margin: usize,
+}
+
+impl Default for Border {
+    fn default() -> Self {
+        Border { motif: Motif::DoubleLine, floss: "310".to_string(), background: "B5200".to_string(), margin: 2 }
+    }
+}
+
+impl Border {
+    /// Stitches added on each side of the chart
+    pub fn inset(&self) -> usize {
+        self.margin + self.motif.thickness()
+    }
+
+    pub fn is_valid(&self, catalog: Catalog) -> bool {
+        catalog.find(&self.floss).is_some() && catalog.find(&self.background).is_some()
+    }
+
+    /// The chart with the border around it.
+    /// Each edge's motif is mirrored about the edge's middle and about the corner diagonals,
+    /// so it meets itself cleanly at the corners whatever the chart size.
+    pub fn frame(&self, chart_data: &ChartData) -> ChartData {
+        let (Some(motif_floss), Some(background_floss)) = (chart_data.catalog.find(&self.floss), chart_data.catalog.find(&self.background)) else {
+            return chart_data.clone();
+        };
+
+        let inset = self.inset();
+        let (width, height) = (chart_data.width + 2 * inset, chart_data.height + 2 * inset);
+        let mut framed = ChartData {
+            width,
+            height,
+            geometry: chart_data.geometry,
+            catalog: chart_data.catalog,
+            palette: chart_data.palette.clone(),
+            stitches: Vec::with_capacity(width * height),
+            backstitches: chart_data.backstitches.iter().map(|line| line.shifted(inset)).collect(),
+        };
+        let motif_index = decorations::palette_index(&mut framed, motif_floss);
+        let background_index = decorations::palette_index(&mut framed, background_floss);
+
+        let thickness = self.motif.thickness();
+        for y in 0..height {
+            for x in 0..width {
+                let inside_x = (inset..inset + chart_data.width).contains(&x);
+                let inside_y = (inset..inset + chart_data.height).contains(&y);
+                if inside_x && inside_y {
+                    framed.stitches.push(chart_data.stitches[x - inset + (y - inset) * chart_data.width]);
+                    continue;
+                }
+
+                // Distance in from the nearest horizontal and vertical outside edges
+                let from_top_or_bottom = y.min(height - 1 - y);
+                let from_side = x.min(width - 1 - x);
+                let (depth, along) = if from_top_or_bottom <= from_side {
+                    (from_top_or_bottom, from_side)
+                } else {
+                    (from_side, from_top_or_bottom)
+                };
+
+                let stitched = depth < thickness && self.motif.stitched(along, depth);
+                framed.stitches.push(if stitched { motif_index } else { background_index });
+            }
+        }
+
+        framed
+    }
+}
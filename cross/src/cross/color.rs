@@ -0,0 +1,92 @@
+//! Perceptual color math, used whenever two stitch colors need to be compared
+use crate::egui::{Color32, Rgba};
+
+/// CIE L*a*b* color, where euclidean distance roughly matches perceived difference.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Lab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+impl Lab {
+    /// CIE76 color difference. Values below ~2.3 are barely noticeable to the eye.
+    pub fn delta_e(&self, other: &Lab) -> f32 {
+        let dl = self.l - other.l;
+        let da = self.a - other.a;
+        let db = self.b - other.b;
+        (dl * dl + da * da + db * db).sqrt()
+    }
+}
+
+impl From<Rgba> for Lab {
+    // https://en.wikipedia.org/wiki/CIELAB_color_space#From_CIEXYZ_to_CIELAB
+    fn from(linear: Rgba) -> Self {
+        // Rgba is already linear, so this goes straight to XYZ (D65), normalized by the white point.
+        let (r, g, b) = (linear.r(), linear.g(), linear.b());
+        let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+        let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+
+        let f = |t: f32| {
+            if t > 0.008856 {
+                t.cbrt()
+            } else {
+                7.787 * t + 16.0 / 116.0
+            }
+        };
+        let (fx, fy, fz) = (f(x), f(y), f(z));
+
+        Lab {
+            l: 116.0 * fy - 16.0,
+            a: 500.0 * (fx - fy),
+            b: 200.0 * (fy - fz),
+        }
+    }
+}
+
+impl From<Color32> for Lab {
+    fn from(color: Color32) -> Self {
+        Lab::from(Rgba::from(color))
+    }
+}
+
+/// Hue, saturation and brightness, each from 0 to 1, to 8-bit sRGB
+pub fn hsb_to_rgb(hue: f32, saturation: f32, brightness: f32) -> [u8; 3] {
+    let sector = (hue.rem_euclid(1.0) * 6.0).min(5.999);
+    let fraction = sector.fract();
+    let (p, q, t) = (
+        brightness * (1.0 - saturation),
+        brightness * (1.0 - saturation * fraction),
+        brightness * (1.0 - saturation * (1.0 - fraction)),
+    );
+
+    let (r, g, b) = match sector as u32 {
+        0 => (brightness, t, p),
+        1 => (q, brightness, p),
+        2 => (p, brightness, t),
+        3 => (p, q, brightness),
+        4 => (t, p, brightness),
+        _ => (brightness, p, q),
+    };
+    [r, g, b].map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+/// 8-bit sRGB to hue, saturation and brightness, each from 0 to 1
+pub fn rgb_to_hsb(color: Color32) -> [f32; 3] {
+    let [r, g, b] = [color.r(), color.g(), color.b()].map(|channel| channel as f32 / 255.0);
+    let max = r.max(g).max(b);
+    let range = max - r.min(g).min(b);
+
+    let hue = if range == 0.0 {
+        0.0
+    } else if max == r {
+        ((g - b) / range).rem_euclid(6.0) / 6.0
+    } else if max == g {
+        ((b - r) / range + 2.0) / 6.0
+    } else {
+        ((r - g) / range + 4.0) / 6.0
+    };
+    let saturation = if max == 0.0 { 0.0 } else { range / max };
+    [hue, saturation, max]
+}
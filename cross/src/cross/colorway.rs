@@ -0,0 +1,130 @@
+//! Alternate colorways of a finished pattern, such as a blue version of a red flower
+use crate::egui::Color32;
+
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+use super::color::{self, Lab};
+use super::config::Config;
+use super::data;
+use super::floss::{self, Catalog, Floss};
+use super::renderer::{ChartData, PaletteColor};
+use super::svg;
+
+/// How every color of the pattern is changed, before any substitutions
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub enum Recolor {
+    // Keeps the pattern's colors, so only substitutions change anything
+    Unchanged,
+    // Turns each color around the color wheel, keeping its lightness and saturation
+    HueRotation { degrees: f32 },
+    // Each color becomes the closest color of another palette, such as one imported from a palette file
+    TargetPalette { colors: Vec<[u8; 3]> },
+}
+
+impl Recolor {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Recolor::Unchanged => "Substitutions only",
+            Recolor::HueRotation { .. } => "Hue rotation",
+            Recolor::TargetPalette { .. } => "Target palette",
+        }
+    }
+
+    fn color(&self, color: Color32) -> Color32 {
+        match self {
+            Recolor::Unchanged => color,
+            Recolor::HueRotation { degrees } => {
+                let [hue, saturation, brightness] = color::rgb_to_hsb(color);
+                let [r, g, b] = color::hsb_to_rgb(hue + degrees / 360.0, saturation, brightness);
+                Color32::from_rgb(r, g, b)
+            },
+            Recolor::TargetPalette { colors } => {
+                let lab = Lab::from(color);
+                colors.iter()
+                    .map(|[r, g, b]| Color32::from_rgb(*r, *g, *b))
+                    .min_by(|a, b| lab.delta_e(&Lab::from(*a)).total_cmp(&lab.delta_e(&Lab::from(*b))))
+                    .unwrap_or(color)
+            },
+        }
+    }
+}
+
+/// One color of the pattern, by floss code (or `code+code` for a blend), stitched in a different floss instead
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Substitution {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Colorway {
+    pub name: String,
+    pub recolor: Recolor,
+    pub substitutions: Vec<Substitution>,
+}
+
+/// Identifies a pattern color by its threads, which stay the same when the chart is re-analyzed.
+pub fn thread_key(color: &PaletteColor) -> String {
+    match color.blend {
+        Some(blend) => format!("{}+{}", color.floss.code, blend.code),
+        None => color.floss.code.to_string(),
+    }
+}
+
+impl Colorway {
+    pub fn new(name: String, recolor: Recolor) -> Self {
+        Colorway { name, recolor, substitutions: vec![] }
+    }
+
+    /// The name, made safe to use in a file name
+    pub fn file_name(&self) -> String {
+        self.name.chars().map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '-' }).collect()
+    }
+
+    pub fn substitution(&self, key: &str) -> Option<&str> {
+        self.substitutions.iter().find(|substitution| substitution.from == key).map(|substitution| substitution.to.as_str())
+    }
+
+    /// Sets the floss code a color is substituted with. An empty code removes the substitution.
+    pub fn substitute(&mut self, key: &str, code: &str) {
+        self.substitutions.retain(|substitution| substitution.from != key);
+        if !code.is_empty() {
+            self.substitutions.push(Substitution { from: key.to_string(), to: code.to_string() });
+        }
+    }
+
+    /// The color a pattern color turns into. Recolored colors are stitched in the closest single floss, from the stash
+    /// if one is given, and colors that the recolor leaves alone keep their threads, blends included.
+    pub fn palette_color(&self, color: &PaletteColor, catalog: Catalog, stash: Option<&[&'static Floss]>) -> PaletteColor {
+        if let Some(floss) = self.substitution(&thread_key(color)).and_then(|code| catalog.find(code)) {
+            return PaletteColor::new(floss.color(), floss);
+        }
+
+        let recolored = self.recolor.color(color.color);
+        if recolored == color.color {
+            return color.clone();
+        }
+        let floss = stash.and_then(|stash| floss::closest_of(stash.iter().copied(), recolored)).unwrap_or_else(|| catalog.closest(recolored));
+        PaletteColor::new(floss.color(), floss)
+    }
+
+    /// The chart in this colorway. Stitches stay as they are; only the palette changes.
+    pub fn apply(&self, chart_data: &ChartData, stash: Option<&[&'static Floss]>) -> ChartData {
+        ChartData {
+            palette: chart_data.palette.iter().map(|color| self.palette_color(color, chart_data.catalog, stash)).collect(),
+            ..chart_data.clone()
+        }
+    }
+
+    /// Saves the chart in this colorway as an SVG chart, a PNG chart image or JSON data, going by the file extension.
+    pub fn save(&self, path: &Path, chart_data: &ChartData, config: &Config, stash: Option<&[&'static Floss]>) -> io::Result<()> {
+        let chart_data = self.apply(chart_data, stash);
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("png") => chart_data.save_image(path),
+            Some("json") => data::save(path, &chart_data, config),
+            _ => std::fs::write(path, svg::to_svg(&chart_data)),
+        }
+    }
+}
@@ -1,4 +1,13 @@
-#[derive(Clone)]
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use super::craft::Craft;
+
+// Settings missing from older saved projects take their default values.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub num_width: i32,
     pub num_height: i32,
@@ -6,16 +15,142 @@ pub struct Config {
     pub num_colors: i32,
     pub num_iterations: i32,
 
+    // Seeds k-means initialization, so the same image and settings always give the same pattern.
+    pub seed: u64,
+
+    // Stitches with fewer matching neighbors than this are recolored. Zero disables cleanup.
+    pub confetti_neighbors: i32,
+
+    // Colors used for fewer stitches than this are merged into the closest remaining color. Zero keeps every color.
+    pub min_color_stitches: i32,
+
+    // Colors whose threads are closer than this ΔE count as near-duplicates, which are merged when enabled.
+    pub merge_duplicates: bool,
+    pub duplicate_delta_e: f32,
+
+    // Limits the palette to flosses in the thread stash.
+    pub use_stash: bool,
+
+    // Also considers two different flosses stitched together, one strand each.
+    pub use_blends: bool,
+
+    // Charts pixel art one stitch per art pixel. The chart size follows the image's pixel grid.
+    pub pixel_art: bool,
+
+    pub craft: Craft,
+
+    // Colors imported from a palette file, quantized to directly instead of k-means. Empty uses k-means.
+    pub fixed_palette: Vec<[u8; 3]>,
+
+    // Knitting gauge: stitches and rows per 10cm
+    pub knit_stitches: i32,
+    pub knit_rows: i32,
+
+    // Longest carry across the back, in stitches, before the stitching order ends the thread instead.
+    // Only used for the stitching order, so changing it doesn't recalculate.
+    pub max_travel: f32,
+
+    // Mean ΔE that suggested color counts aim for. Only used for suggestions, so changing it doesn't recalculate.
+    pub target_error: f32,
+
+    #[serde(skip)]
     last_width: i32,
+    #[serde(skip)]
     last_height: i32,
+    #[serde(skip)]
     last_days: i32,
+    #[serde(skip)]
     last_colors: i32,
+    #[serde(skip)]
     last_iterations: i32,
+    #[serde(skip)]
+    last_seed: u64,
+    #[serde(skip)]
+    last_confetti_neighbors: i32,
+    #[serde(skip)]
+    last_min_color_stitches: i32,
+    #[serde(skip)]
+    last_merge_duplicates: bool,
+    #[serde(skip)]
+    last_duplicate_delta_e: f32,
+    #[serde(skip)]
+    last_use_stash: bool,
+    #[serde(skip)]
+    last_use_blends: bool,
+    #[serde(skip)]
+    last_pixel_art: bool,
+    #[serde(skip)]
+    last_craft: Craft,
+    #[serde(skip)]
+    last_fixed_palette: Vec<[u8; 3]>,
+    #[serde(skip)]
+    last_knit_stitches: i32,
+    #[serde(skip)]
+    last_knit_rows: i32,
 }
 
-const PIXELS_PER_DAY_AVG: f64 = 80.0;
+pub const PIXELS_PER_DAY_AVG: f64 = 80.0;
+
+// Ranges the settings can be set to in the app. Loaded presets and projects are clamped to them.
+pub const SIZE_RANGE: RangeInclusive<i32> = 10..=500;
+pub const DAYS_RANGE: RangeInclusive<i32> = 1..=365;
+pub const COLORS_RANGE: RangeInclusive<i32> = 2..=50;
+pub const ITERATIONS_RANGE: RangeInclusive<i32> = 1..=100;
+pub const CONFETTI_RANGE: RangeInclusive<i32> = 0..=4;
+pub const MIN_COLOR_STITCHES_RANGE: RangeInclusive<i32> = 0..=200;
+pub const DUPLICATE_DELTA_E_RANGE: RangeInclusive<f32> = 0.5..=10.0;
+pub const KNIT_STITCHES_RANGE: RangeInclusive<i32> = 5..=60;
+pub const KNIT_ROWS_RANGE: RangeInclusive<i32> = 5..=80;
+pub const MAX_TRAVEL_RANGE: RangeInclusive<f32> = 1.0..=50.0;
+pub const TARGET_ERROR_RANGE: RangeInclusive<f32> = 1.0..=15.0;
+
+fn clamp<T: PartialOrd + Copy>(value: &mut T, range: &RangeInclusive<T>) {
+    if *value < *range.start() {
+        *value = *range.start();
+    } else if *value > *range.end() {
+        *value = *range.end();
+    }
+}
 
 impl Config {
+    /// Saves just the settings, as a preset for other images.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Config> {
+        let mut config: Config = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        config.clamp_to_ranges();
+        config.sync_columns();
+        Ok(config)
+    }
+
+    /// Brings every setting into the range the app offers, as a preset or project may have been edited by hand.
+    pub fn clamp_to_ranges(&mut self) {
+        clamp(&mut self.num_width, &SIZE_RANGE);
+        clamp(&mut self.num_height, &SIZE_RANGE);
+        clamp(&mut self.num_days, &DAYS_RANGE);
+        clamp(&mut self.num_colors, &COLORS_RANGE);
+        clamp(&mut self.num_iterations, &ITERATIONS_RANGE);
+        clamp(&mut self.confetti_neighbors, &CONFETTI_RANGE);
+        clamp(&mut self.min_color_stitches, &MIN_COLOR_STITCHES_RANGE);
+        clamp(&mut self.duplicate_delta_e, &DUPLICATE_DELTA_E_RANGE);
+        clamp(&mut self.knit_stitches, &KNIT_STITCHES_RANGE);
+        clamp(&mut self.knit_rows, &KNIT_ROWS_RANGE);
+        clamp(&mut self.max_travel, &MAX_TRAVEL_RANGE);
+        clamp(&mut self.target_error, &TARGET_ERROR_RANGE);
+    }
+
+    /// Sets the chart size, with the days following it as they do when the size is changed in the app.
+    pub fn set_size(&mut self, width: i32, height: i32) {
+        if (width, height) != (self.num_width, self.num_height) {
+            self.num_width = width;
+            self.num_height = height;
+            self.num_days = (width * height) / (PIXELS_PER_DAY_AVG as i32);
+            clamp(&mut self.num_days, &DAYS_RANGE);
+        }
+    }
+
     pub fn recalculate_columns(&mut self) -> bool {
         let mut recalculate = false;
 
@@ -52,15 +187,79 @@ impl Config {
             recalculate = true;
         }
 
+        if self.last_seed != self.seed {
+            self.sync_columns();
+            recalculate = true;
+        }
+
+        if self.last_confetti_neighbors != self.confetti_neighbors {
+            self.sync_columns();
+            recalculate = true;
+        }
+
+        if self.last_min_color_stitches != self.min_color_stitches {
+            self.sync_columns();
+            recalculate = true;
+        }
+
+        // The threshold only changes the chart while merging. Otherwise it just changes the palette warning.
+        if self.last_merge_duplicates != self.merge_duplicates || (self.merge_duplicates && self.last_duplicate_delta_e != self.duplicate_delta_e) {
+            self.sync_columns();
+            recalculate = true;
+        }
+
+        if self.last_use_stash != self.use_stash {
+            self.sync_columns();
+            recalculate = true;
+        }
+
+        if self.last_use_blends != self.use_blends {
+            self.sync_columns();
+            recalculate = true;
+        }
+
+        if self.last_pixel_art != self.pixel_art {
+            self.sync_columns();
+            recalculate = true;
+        }
+
+        if self.last_craft != self.craft {
+            self.sync_columns();
+            recalculate = true;
+        }
+
+        if self.last_fixed_palette != self.fixed_palette {
+            self.sync_columns();
+            recalculate = true;
+        }
+
+        if self.last_knit_stitches != self.knit_stitches || self.last_knit_rows != self.knit_rows {
+            self.sync_columns();
+            recalculate = true;
+        }
+
         recalculate
     }
 
-    fn sync_columns(&mut self) {
+    /// Marks the current settings as already applied, so they don't trigger a recalculation.
+    pub fn sync_columns(&mut self) {
         self.last_width = self.num_width;
         self.last_height = self.num_height;
         self.last_days = self.num_days;
         self.last_colors = self.num_colors;
         self.last_iterations = self.num_iterations;
+        self.last_seed = self.seed;
+        self.last_confetti_neighbors = self.confetti_neighbors;
+        self.last_min_color_stitches = self.min_color_stitches;
+        self.last_merge_duplicates = self.merge_duplicates;
+        self.last_duplicate_delta_e = self.duplicate_delta_e;
+        self.last_use_stash = self.use_stash;
+        self.last_use_blends = self.use_blends;
+        self.last_pixel_art = self.pixel_art;
+        self.last_craft = self.craft;
+        self.last_fixed_palette = self.fixed_palette.clone();
+        self.last_knit_stitches = self.knit_stitches;
+        self.last_knit_rows = self.knit_rows;
     }
 }
 
@@ -72,12 +271,38 @@ impl Default for Config {
             num_days: 15,
             num_colors: 24,
             num_iterations: 50,
+            seed: 0,
+            confetti_neighbors: 0,
+            min_color_stitches: 0,
+            merge_duplicates: false,
+            duplicate_delta_e: 3.0,
+            use_stash: false,
+            use_blends: false,
+            pixel_art: false,
+            craft: Craft::CrossStitch,
+            fixed_palette: vec![],
+            knit_stitches: 22,
+            knit_rows: 30,
+            max_travel: 5.0,
+            target_error: 5.0,
 
             last_width: -1,
             last_height: -1,
             last_days: -1,
             last_colors: -1,
             last_iterations: -1,
+            last_seed: 0,
+            last_confetti_neighbors: -1,
+            last_min_color_stitches: -1,
+            last_merge_duplicates: false,
+            last_duplicate_delta_e: -1.0,
+            last_use_stash: false,
+            last_use_blends: false,
+            last_pixel_art: false,
+            last_craft: Craft::CrossStitch,
+            last_fixed_palette: vec![],
+            last_knit_stitches: -1,
+            last_knit_rows: -1,
         };
 
         default_config.sync_columns();
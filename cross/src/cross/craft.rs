@@ -0,0 +1,146 @@
+//! Crafts that can be charted, and how each one's cells are shaped and its materials counted
+use serde::{Deserialize, Serialize};
+
+use super::config::Config;
+use super::floss::{self, Catalog};
+
+// Approximate sizes of one cell, in millimeters
+const AIDA_14_MM: f32 = 25.4 / 14.0;
+const DELICA_MM: f32 = 1.6;
+const DRILL_MM: f32 = 2.5;
+
+// Yarn used by one knit stitch (including its share of the float behind it), in stitch widths.
+const KNIT_YARN_PER_STITCH: f32 = 4.0;
+
+// Delica 11/0 beads per gram
+const BEADS_PER_GRAM: f32 = 190.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Craft {
+    #[default]
+    CrossStitch,
+    // Stranded colorwork, charted by gauge, so stitches are wider than tall
+    Knitting,
+    // Beadwork where alternate columns sit half a bead lower
+    Peyote,
+    // Beadwork where alternate rows sit half a bead to the right
+    Brick,
+    // Square drills on a DMC-numbered canvas
+    DiamondPainting,
+}
+
+/// Which cells are shifted by half a cell, as in beadwork.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum Offset {
+    #[default]
+    None,
+    // Odd rows are shifted right
+    Rows,
+    // Odd columns are shifted down
+    Columns,
+}
+
+/// Shape of the cell grid, as drawn on the chart.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Geometry {
+    // Cell width divided by cell height
+    pub aspect: f32,
+    pub offset: Offset,
+
+    // Cells are drawn as individual round pieces (drills) rather than a grid of squares
+    pub round_cells: bool,
+}
+
+impl Default for Geometry {
+    fn default() -> Self {
+        Geometry { aspect: 1.0, offset: Offset::None, round_cells: false }
+    }
+}
+
+impl Craft {
+    pub const ALL: [Craft; 5] = [Craft::CrossStitch, Craft::Knitting, Craft::Peyote, Craft::Brick, Craft::DiamondPainting];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Craft::CrossStitch => "Cross stitch",
+            Craft::Knitting => "Colorwork knitting",
+            Craft::Peyote => "Peyote beadwork",
+            Craft::Brick => "Brick stitch beadwork",
+            Craft::DiamondPainting => "Diamond painting",
+        }
+    }
+
+    /// Width and height of one cell, in millimeters.
+    pub fn cell_mm(self, config: &Config) -> [f32; 2] {
+        match self {
+            Craft::CrossStitch => [AIDA_14_MM, AIDA_14_MM],
+            Craft::Knitting => [100.0 / config.knit_stitches.max(1) as f32, 100.0 / config.knit_rows.max(1) as f32],
+            Craft::Peyote | Craft::Brick => [DELICA_MM, DELICA_MM],
+            Craft::DiamondPainting => [DRILL_MM, DRILL_MM],
+        }
+    }
+
+    pub fn geometry(self, config: &Config) -> Geometry {
+        let [width, height] = self.cell_mm(config);
+        Geometry {
+            aspect: width / height,
+            offset: match self {
+                Craft::Peyote => Offset::Columns,
+                Craft::Brick => Offset::Rows,
+                _ => Offset::None,
+            },
+            round_cells: self == Craft::DiamondPainting,
+        }
+    }
+
+    /// Floss and diamond painting drills go by DMC numbers. Yarn and beads have their own catalogs.
+    pub fn catalog(self) -> Catalog {
+        match self {
+            Craft::CrossStitch | Craft::DiamondPainting => Catalog::Dmc,
+            Craft::Knitting => Catalog::Yarn,
+            Craft::Peyote | Craft::Brick => Catalog::Delica,
+        }
+    }
+
+    /// Only floss can have two colors in the needle. Yarn, beads and drills are a single color each.
+    pub fn allows_blends(self) -> bool {
+        self == Craft::CrossStitch
+    }
+
+    /// How much material the given number of cells takes, in the units it's bought in.
+    pub fn describe_usage(self, cells: f32, config: &Config) -> String {
+        match self {
+            Craft::CrossStitch => format!("about {:.1} m", floss::meters_needed(cells)),
+            Craft::Knitting => format!("about {:.1} m of yarn", self.yarn_meters(cells, config)),
+            Craft::Peyote | Craft::Brick => format!("{:.0} beads, about {:.1} g", cells, cells / BEADS_PER_GRAM),
+            Craft::DiamondPainting => format!("{:.0} drills", cells),
+        }
+    }
+
+    /// CSV header for the material columns, matching `usage_fields`.
+    pub fn usage_columns(self) -> &'static str {
+        match self {
+            Craft::CrossStitch => "meters,skeins",
+            Craft::Knitting => "meters",
+            Craft::Peyote | Craft::Brick => "beads,grams",
+            Craft::DiamondPainting => "drills",
+        }
+    }
+
+    /// The material the given number of cells takes, as CSV fields under `usage_columns`.
+    pub fn usage_fields(self, cells: f32, config: &Config) -> String {
+        match self {
+            Craft::CrossStitch => {
+                let meters = floss::meters_needed(cells);
+                format!("{:.2},{}", meters, (meters / floss::SKEIN_METERS).ceil())
+            }
+            Craft::Knitting => format!("{:.2}", self.yarn_meters(cells, config)),
+            Craft::Peyote | Craft::Brick => format!("{},{:.2}", cells.ceil(), cells / BEADS_PER_GRAM),
+            Craft::DiamondPainting => format!("{}", cells.ceil()),
+        }
+    }
+
+    fn yarn_meters(self, cells: f32, config: &Config) -> f32 {
+        cells * KNIT_YARN_PER_STITCH * self.cell_mm(config)[0] / 1000.0
+    }
+}
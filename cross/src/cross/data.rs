@@ -0,0 +1,159 @@
+//! Raw pattern data for other tools to read, as JSON and CSV.
+//!
+//! Schema version 2. `<stem>.json` holds:
+//! - `schema_version`: 2
+//! - `width`, `height`: chart size in stitches
+//! - `palette`: one entry per palette color, in order, each with:
+//!   - `index`: position in the palette, as used by `stitches`
+//!   - `rgb`: chart color as `[r, g, b]`
+//!   - `floss`: catalog code, and `blend`: catalog code of the second floss of a blend, or null.
+//!     The catalog follows `config.craft`: DMC for cross stitch and diamond painting, Miyuki Delica for beadwork,
+//!     and generic yarn colors for knitting, which go by their names.
+//!   - `symbol`: chart symbol
+//!   - `stitches`: number of stitches in this color
+//! - `stitches`: palette index of each stitch, row-major from the top-left, `width * height` long
+//! - `config`: the settings the chart was made with, as saved in projects
+//!
+//! `<stem>-stitches.csv` has one row per stitch, in the same order:
+//! `x,y,palette_index,symbol,floss,blend,r,g,b`, with `blend` empty for single-floss colors.
+//!
+//! `<stem>-floss.csv` has one row per floss used, in palette order:
+//! `floss,name,r,g,b,stitches,` followed by the amount to buy, in the units of `config.craft`:
+//! - cross stitch: `meters,skeins`
+//! - knitting: `meters` of yarn
+//! - beadwork: `beads,grams`
+//! - diamond painting: `drills`
+//!
+//! A blended stitch counts as half a stitch of each of its flosses. Version 1 always wrote `meters,skeins`.
+//!
+//! Fields are only ever added within a version. Renaming, removing or changing the meaning of one bumps it.
+use serde::Serialize;
+
+use std::fmt::Write;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::config::Config;
+use super::renderer::ChartData;
+
+pub const SCHEMA_VERSION: u32 = 2;
+
+#[derive(Serialize)]
+struct DataColor {
+    index: usize,
+    rgb: [u8; 3],
+    floss: String,
+    blend: Option<String>,
+    symbol: char,
+    stitches: usize,
+}
+
+#[derive(Serialize)]
+struct PatternData<'a> {
+    schema_version: u32,
+    width: usize,
+    height: usize,
+    palette: Vec<DataColor>,
+    stitches: &'a [usize],
+    config: &'a Config,
+}
+
+pub fn to_json(chart_data: &ChartData, config: &Config) -> serde_json::Result<String> {
+    let symbols = chart_data.symbols();
+    let counts = chart_data.color_counts();
+    serde_json::to_string_pretty(&PatternData {
+        schema_version: SCHEMA_VERSION,
+        width: chart_data.width,
+        height: chart_data.height,
+        palette: chart_data.palette.iter().enumerate().map(|(index, color)| DataColor {
+            index,
+            rgb: [color.color.r(), color.color.g(), color.color.b()],
+            floss: color.floss.code.to_string(),
+            blend: color.blend.map(|blend| blend.code.to_string()),
+            symbol: symbols[index],
+            stitches: counts[index],
+        }).collect(),
+        stitches: &chart_data.stitches,
+        config,
+    })
+}
+
+pub fn stitches_csv(chart_data: &ChartData) -> String {
+    let symbols = chart_data.symbols();
+    let mut csv = String::from("x,y,palette_index,symbol,floss,blend,r,g,b\n");
+    for (stitch, palette_index) in chart_data.stitches.iter().enumerate() {
+        let color = &chart_data.palette[*palette_index];
+        _ = writeln!(csv, "{},{},{},{},{},{},{},{},{}",
+            stitch % chart_data.width, stitch / chart_data.width, palette_index, csv_field(&symbols[*palette_index].to_string()),
+            color.floss.code, color.blend.map_or("", |blend| blend.code), color.color.r(), color.color.g(), color.color.b());
+    }
+
+    csv
+}
+
+pub fn floss_csv(chart_data: &ChartData, config: &Config) -> String {
+    let mut csv = format!("floss,name,r,g,b,stitches,{}\n", config.craft.usage_columns());
+    for (floss, stitches) in chart_data.floss_usage() {
+        _ = writeln!(csv, "{},{},{},{},{},{},{}",
+            floss.code, csv_field(floss.name), floss.rgb[0], floss.rgb[1], floss.rgb[2], stitches, config.craft.usage_fields(stitches, config));
+    }
+
+    csv
+}
+
+// Quotes fields that would otherwise break the row, such as the ',' symbol
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// The CSV files written alongside the JSON file, named after it
+fn csv_paths(json_path: &Path) -> (PathBuf, PathBuf) {
+    let stem = json_path.file_stem().map_or_else(|| "pattern".to_string(), |stem| stem.to_string_lossy().to_string());
+    (json_path.with_file_name(format!("{}-stitches.csv", stem)), json_path.with_file_name(format!("{}-floss.csv", stem)))
+}
+
+/// Writes the JSON file, and both CSV files next to it.
+pub fn save(json_path: &Path, chart_data: &ChartData, config: &Config) -> io::Result<()> {
+    std::fs::write(json_path, to_json(chart_data, config)?)?;
+
+    let (stitches_path, floss_path) = csv_paths(json_path);
+    std::fs::write(stitches_path, stitches_csv(chart_data))?;
+    std::fs::write(floss_path, floss_csv(chart_data, config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cross::craft::{Craft, Geometry};
+    use crate::cross::renderer::PaletteColor;
+
+    fn floss_csv_for(craft: Craft, code: &str) -> String {
+        let catalog = craft.catalog();
+        let floss = catalog.find(code).expect("floss is in the catalog");
+        let chart_data = ChartData {
+            width: 20,
+            height: 10,
+            geometry: Geometry::default(),
+            catalog,
+            palette: vec![PaletteColor::new(floss.color(), floss)],
+            stitches: vec![0; 200],
+            backstitches: vec![],
+        };
+        let mut config = Config::default();
+        config.craft = craft;
+        floss_csv(&chart_data, &config)
+    }
+
+    #[test]
+    fn floss_csv_counts_in_each_crafts_units() {
+        let rows = |csv: String| csv.lines().map(|line| line.split(',').skip(5).collect::<Vec<_>>().join(",")).collect::<Vec<_>>();
+        assert_eq!(rows(floss_csv_for(Craft::CrossStitch, "310")), ["stitches,meters,skeins", "200,0.89,1"]);
+        assert_eq!(rows(floss_csv_for(Craft::Peyote, "DB-0010")), ["stitches,beads,grams", "200,200,1.05"]);
+        assert_eq!(rows(floss_csv_for(Craft::DiamondPainting, "310")), ["stitches,drills", "200,200"]);
+        assert_eq!(rows(floss_csv_for(Craft::Knitting, "Black")), ["stitches,meters", "200,3.64"]);
+    }
+}
@@ -0,0 +1,260 @@
+//! Text and other additions stitched over the analyzed chart, kept separate so they survive reanalysis
+use crate::egui::{Color32, ColorImage};
+
+use serde::{Deserialize, Serialize};
+
+use super::border::Border;
+use super::floss::Floss;
+use super::lettering::Bitmap;
+use super::craft::Offset;
+use super::renderer::{Backstitch, ChartData, PaletteColor};
+use super::repeat::Repeat;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PlacedText {
+    pub text: String,
+
+    // Code in the chart's catalog
+    pub floss: String,
+
+    // Top-left stitch, on the analyzed chart
+    pub x: usize,
+    pub y: usize,
+
+    // Kept rendered, so the font used doesn't need to be around to reopen the project
+    pub bitmap: Bitmap,
+}
+
+/// A single stitch changed by hand, on the analyzed chart.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StitchEdit {
+    pub x: usize,
+    pub y: usize,
+
+    // Code in the chart's catalog
+    pub floss: String,
+}
+
+/// A backstitch line between two corners of the analyzed chart's stitch grid.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackstitchLine {
+    pub from: [usize; 2],
+    pub to: [usize; 2],
+
+    // Code in the chart's catalog
+    pub floss: String,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Decorations {
+    #[serde(default)]
+    pub texts: Vec<PlacedText>,
+
+    #[serde(default)]
+    pub edits: Vec<StitchEdit>,
+
+    #[serde(default)]
+    pub backstitches: Vec<BackstitchLine>,
+
+    // Copies a tile of the chart (with any text and edits in it) over the rest
+    #[serde(default)]
+    pub repeat: Option<Repeat>,
+
+    // Goes around everything else, extending the chart
+    #[serde(default)]
+    pub border: Option<Border>,
+}
+
+impl Decorations {
+    /// The analyzed chart with everything stitched over it.
+    pub fn apply(&self, chart_data: &ChartData) -> ChartData {
+        let mut decorated = chart_data.clone();
+        for text in &self.texts {
+            if let Some(floss) = decorated.catalog.find(&text.floss) {
+                stamp(&mut decorated, &text.bitmap, text.x, text.y, floss);
+            }
+        }
+
+        for edit in &self.edits {
+            if let Some(floss) = decorated.catalog.find(&edit.floss) {
+                if edit.x < decorated.width && edit.y < decorated.height {
+                    let index = palette_index(&mut decorated, floss);
+                    decorated.stitches[edit.x + edit.y * decorated.width] = index;
+                }
+            }
+        }
+
+        if let Some(repeat) = &self.repeat {
+            repeat.apply(&mut decorated);
+        }
+
+        // Offset grids have no straight lines between corners to stitch along.
+        if decorated.geometry.offset == Offset::None {
+            let in_chart = |[x, y]: [usize; 2]| x <= chart_data.width && y <= chart_data.height;
+            for line in self.backstitches.iter().filter(|line| in_chart(line.from) && in_chart(line.to)) {
+                if let Some(floss) = decorated.catalog.find(&line.floss) {
+                    decorated.backstitches.push(Backstitch { from: line.from, to: line.to, floss });
+                }
+            }
+        }
+
+        match &self.border {
+            Some(border) => border.frame(&decorated),
+            None => decorated,
+        }
+    }
+
+    /// Sets a stitch to the floss, or with no floss, takes back any edit to it.
+    pub fn edit(&mut self, x: usize, y: usize, floss: Option<&str>) {
+        self.edits.retain(|edit| edit.x != x || edit.y != y);
+        if let Some(floss) = floss {
+            self.edits.push(StitchEdit { x, y, floss: floss.to_string() });
+        }
+    }
+
+    /// Adds a backstitch line, unless it has no length or is already there.
+    pub fn add_backstitch(&mut self, from: [usize; 2], to: [usize; 2], floss: &str) {
+        let same = |line: &BackstitchLine| (line.from == from && line.to == to) || (line.from == to && line.to == from);
+        if from != to && !self.backstitches.iter().any(same) {
+            self.backstitches.push(BackstitchLine { from, to, floss: floss.to_string() });
+        }
+    }
+
+    /// Takes out every backstitch line ending at a grid corner.
+    pub fn remove_backstitches_at(&mut self, corner: [usize; 2]) {
+        self.backstitches.retain(|line| line.from != corner && line.to != corner);
+    }
+
+    /// Stitches added on each side of the analyzed chart
+    pub fn inset(&self) -> usize {
+        self.border.as_ref().map_or(0, Border::inset)
+    }
+
+    /// The analyzed chart's stitch under a stitch of the decorated chart, or the nearest one if it's in the border.
+    pub fn analyzed_stitch(&self, chart_data: &ChartData, stitch: usize) -> usize {
+        let inset = self.inset();
+        let (width, height) = (chart_data.width - 2 * inset, chart_data.height - 2 * inset);
+        let x = (stitch % chart_data.width).saturating_sub(inset).min(width - 1);
+        let y = (stitch / chart_data.width).saturating_sub(inset).min(height - 1);
+        x + y * width
+    }
+
+    /// The analyzed chart's grid corner at a corner of the decorated chart, or None if it's out in the border.
+    pub fn analyzed_corner(&self, chart_data: &ChartData, [x, y]: [usize; 2]) -> Option<[usize; 2]> {
+        let inset = self.inset();
+        let (x, y) = (x.checked_sub(inset)?, y.checked_sub(inset)?);
+        (x <= chart_data.width - 2 * inset && y <= chart_data.height - 2 * inset).then_some([x, y])
+    }
+
+    /// Places an overlay made for the analyzed chart over the same stitches of the decorated chart.
+    pub fn pad_overlay(&self, image: ColorImage) -> ColorImage {
+        let inset = self.inset();
+        if inset == 0 {
+            return image;
+        }
+
+        let [width, height] = image.size;
+        let padded_width = width + 2 * inset;
+        let mut padded = ColorImage::new([padded_width, height + 2 * inset], Color32::TRANSPARENT);
+        for y in 0..height {
+            let start = inset + (y + inset) * padded_width;
+            padded.pixels[start..start + width].copy_from_slice(&image.pixels[y * width..(y + 1) * width]);
+        }
+
+        padded
+    }
+}
+
+/// Index of the floss in the palette, adding it if the chart doesn't use it on its own yet.
+pub fn palette_index(chart_data: &mut ChartData, floss: &'static Floss) -> usize {
+    match chart_data.palette.iter().position(|color| color.blend.is_none() && color.floss.code == floss.code) {
+        Some(index) => index,
+        None => {
+            chart_data.palette.push(PaletteColor::new(floss.color(), floss));
+            chart_data.palette.len() - 1
+        }
+    }
+}
+
+/// Sets the bitmap's stitches to the floss, clipped to the chart.
+pub fn stamp(chart_data: &mut ChartData, bitmap: &Bitmap, left: usize, top: usize, floss: &'static Floss) {
+    let index = palette_index(chart_data, floss);
+    for y in 0..bitmap.height.min(chart_data.height.saturating_sub(top)) {
+        for x in 0..bitmap.width.min(chart_data.width.saturating_sub(left)) {
+            if bitmap.get(x, y) {
+                chart_data.stitches[left + x + (top + y) * chart_data.width] = index;
+            }
+        }
+    }
+}
+
+/// Top-left stitch that centers the bitmap on the given stitch, nudged to keep it on the chart.
+pub fn centered_origin(chart_data: &ChartData, bitmap: &Bitmap, stitch: usize) -> (usize, usize) {
+    let (x, y) = (stitch % chart_data.width, stitch / chart_data.width);
+    (
+        x.saturating_sub(bitmap.width / 2).min(chart_data.width.saturating_sub(bitmap.width)),
+        y.saturating_sub(bitmap.height / 2).min(chart_data.height.saturating_sub(bitmap.height)),
+    )
+}
+
+/// Shows where the bitmap would go, for drawing over the chart.
+pub fn preview(chart_data: &ChartData, bitmap: &Bitmap, left: usize, top: usize, color: Color32) -> ColorImage {
+    let tint = Color32::from_rgba_unmultiplied(color.r(), color.g(), color.b(), 200);
+    let mut image = ColorImage::new([chart_data.width, chart_data.height], Color32::TRANSPARENT);
+    for y in 0..bitmap.height.min(chart_data.height.saturating_sub(top)) {
+        for x in 0..bitmap.width.min(chart_data.width.saturating_sub(left)) {
+            if bitmap.get(x, y) {
+                image.pixels[left + x + (top + y) * chart_data.width] = tint;
+            }
+        }
+    }
+
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cross::border::Border;
+    use crate::cross::craft::Geometry;
+    use crate::cross::floss::Catalog;
+
+    fn chart(width: usize, height: usize) -> ChartData {
+        let floss = Catalog::Dmc.find("B5200").expect("DMC has B5200");
+        ChartData {
+            width,
+            height,
+            geometry: Geometry::default(),
+            catalog: Catalog::Dmc,
+            palette: vec![PaletteColor::new(floss.color(), floss)],
+            stitches: vec![0; width * height],
+            backstitches: vec![],
+        }
+    }
+
+    #[test]
+    fn backstitch_follows_the_border_and_stays_on_the_chart() {
+        let mut decorations = Decorations::default();
+        decorations.add_backstitch([0, 0], [3, 2], "310");
+        decorations.add_backstitch([3, 2], [0, 0], "310");
+        decorations.add_backstitch([1, 1], [1, 1], "310");
+        decorations.add_backstitch([0, 0], [4, 0], "310");
+        decorations.add_backstitch([0, 1], [1, 0], "not a floss");
+        assert_eq!(decorations.backstitches.len(), 3);
+
+        let analyzed = chart(3, 2);
+        let lines: Vec<([usize; 2], [usize; 2])> = decorations.apply(&analyzed).backstitches.iter().map(|line| (line.from, line.to)).collect();
+        assert_eq!(lines, [([0, 0], [3, 2])]);
+
+        decorations.border = Some(Border::default());
+        let inset = decorations.inset();
+        let framed = decorations.apply(&analyzed);
+        assert_eq!(framed.backstitches[0].from, [inset, inset]);
+        assert_eq!(framed.backstitches[0].to, [3 + inset, 2 + inset]);
+        assert_eq!(decorations.analyzed_corner(&framed, [inset + 1, inset]), Some([1, 0]));
+        assert_eq!(decorations.analyzed_corner(&framed, [inset - 1, inset]), None);
+
+        decorations.remove_backstitches_at([3, 2]);
+        assert_eq!(decorations.backstitches.len(), 2);
+    }
+}
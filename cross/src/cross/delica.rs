@@ -0,0 +1,43 @@
+//! Miyuki Delica 11/0 cylinder bead catalog, for peyote and brick stitch charts
+use super::floss::Floss;
+
+const fn bead(code: &'static str, name: &'static str, r: u8, g: u8, b: u8) -> Floss {
+    Floss { brand: "Miyuki", code, name, rgb: [r, g, b] }
+}
+
+// A selection of long-running opaque, ceylon and metallic colors, as RGB approximations of the beads.
+// Finishes such as lined or iris beads shift in the light, so these are their overall look.
+pub const DELICA: &[Floss] = &[
+    bead("DB-0001", "Gunmetal", 62, 62, 68),
+    bead("DB-0002", "Dark Blue Iris", 42, 46, 82),
+    bead("DB-0010", "Black", 24, 24, 26),
+    bead("DB-0011", "Olive", 82, 84, 50),
+    bead("DB-0012", "Raspberry", 112, 40, 62),
+    bead("DB-0022", "Bronze", 122, 92, 60),
+    bead("DB-0031", "24kt Gold Plated", 198, 160, 74),
+    bead("DB-0035", "Galvanized Silver", 186, 188, 192),
+    bead("DB-0041", "Silver Lined Crystal", 222, 224, 226),
+    bead("DB-0200", "Opaque Chalk White", 244, 243, 238),
+    bead("DB-0201", "White Pearl Ceylon", 238, 234, 222),
+    bead("DB-0203", "Cream Ceylon", 236, 222, 190),
+    bead("DB-0206", "Pink Ceylon", 238, 196, 196),
+    bead("DB-0210", "Gray Ceylon", 190, 190, 190),
+    bead("DB-0310", "Matte Black", 36, 36, 38),
+    bead("DB-0351", "Matte White", 232, 232, 228),
+    bead("DB-0651", "Dyed Opaque Squash", 240, 168, 48),
+    bead("DB-0653", "Dyed Opaque Pumpkin", 232, 108, 36),
+    bead("DB-0654", "Dyed Opaque Maroon", 120, 28, 40),
+    bead("DB-0661", "Dyed Opaque Bright Purple", 120, 48, 140),
+    bead("DB-0721", "Opaque Yellow", 248, 206, 40),
+    bead("DB-0722", "Opaque Orange", 238, 122, 34),
+    bead("DB-0723", "Opaque Red", 196, 32, 40),
+    bead("DB-0724", "Opaque Green", 36, 126, 64),
+    bead("DB-0726", "Opaque Navy Blue", 30, 42, 96),
+    bead("DB-0727", "Opaque Vermillion Red", 222, 60, 40),
+    bead("DB-0728", "Opaque Cobalt", 30, 70, 160),
+    bead("DB-0729", "Opaque Turquoise Green", 30, 150, 140),
+    bead("DB-0732", "Opaque Canary", 250, 226, 90),
+    bead("DB-0733", "Opaque Chartreuse", 170, 196, 40),
+    bead("DB-0734", "Opaque Tan", 200, 160, 110),
+    bead("DB-1490", "Opaque Bisque White", 236, 226, 206),
+];
@@ -0,0 +1,541 @@
+//! Plans machine-embroidery stitches that sew each chart cell as an X, and writes them as Tajima DST or Brother PES
+use crate::egui::{Color32, ColorImage, Rgba};
+
+use super::color::Lab;
+use super::renderer::ChartData;
+
+// Machine coordinates are in tenths of a millimeter.
+const UNITS_PER_MM: f32 = 10.0;
+
+// Longest move a single DST record can hold, in machine units
+const DST_MAX_MOVE: i32 = 121;
+const DST_HEADER_SIZE: usize = 512;
+
+// Longest side of the stitch path preview, in pixels
+const PREVIEW_SIZE: f32 = 600.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Stitch,
+    // Moves without sewing, leaving a thread to trim
+    Jump,
+    // Stops for the next thread. The needle stays put.
+    ColorChange,
+    End,
+}
+
+/// One needle position, relative to the middle of the design.
+#[derive(Clone, Copy)]
+pub struct Needle {
+    pub x: i32,
+    pub y: i32,
+    pub command: Command,
+}
+
+pub struct StitchPlan {
+    pub needles: Vec<Needle>,
+
+    // Thread color of each block between color changes
+    pub colors: Vec<Color32>,
+}
+
+/// Cells of one color next to each other in a row, from column `first` to `last`.
+struct Run {
+    first: usize,
+    last: usize,
+}
+
+impl StitchPlan {
+    /// Sews each color in turn, row by row. Each row is sewn out and back: the way out zig-zags between the top
+    /// and bottom of the row, sewing every other diagonal, and the way back zig-zags the other way, sewing the rest,
+    /// so every cell ends up as an X without any stitch along a cell's edge.
+    /// The needle jumps between runs of cells, and once per run where the way out turns back.
+    pub fn new(chart_data: &ChartData, cell_mm: [f32; 2]) -> Self {
+        let corner_x = |x: usize| ((x as f32 - chart_data.width as f32 / 2.0) * cell_mm[0] * UNITS_PER_MM).round() as i32;
+        let corner_y = |y: usize| ((y as f32 - chart_data.height as f32 / 2.0) * cell_mm[1] * UNITS_PER_MM).round() as i32;
+
+        let mut plan = StitchPlan { needles: vec![], colors: vec![] };
+        let mut position = (0, 0);
+        for (palette_index, count) in chart_data.color_counts().into_iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+
+            if !plan.colors.is_empty() {
+                plan.needles.push(Needle { x: position.0, y: position.1, command: Command::ColorChange });
+            }
+            plan.colors.push(chart_data.palette[palette_index].thread_color());
+
+            for y in 0..chart_data.height {
+                let row = &chart_data.stitches[y * chart_data.width..(y + 1) * chart_data.width];
+                let mut runs = vec![];
+                let mut x = 0;
+                while x < chart_data.width {
+                    if row[x] != palette_index {
+                        x += 1;
+                        continue;
+                    }
+
+                    let first = x;
+                    while x < chart_data.width && row[x] == palette_index {
+                        x += 1;
+                    }
+                    runs.push(Run { first, last: x - 1 });
+                }
+
+                let (top, bottom) = (corner_y(y), corner_y(y + 1));
+                for returning in [false, true] {
+                    if returning {
+                        runs.reverse();
+                    }
+
+                    for run in &runs {
+                        // Cell edges, from the first cell's left to the last cell's right (or back), alternating top and bottom.
+                        // On the way out the first edge is at the top, and on the way back at the bottom.
+                        let mut edges: Vec<usize> = (run.first..=run.last + 1).collect();
+                        if returning {
+                            edges.reverse();
+                        }
+
+                        for (i, edge) in edges.into_iter().enumerate() {
+                            let at_top = (edge - run.first) % 2 == usize::from(returning);
+                            let corner = (corner_x(edge), if at_top { top } else { bottom });
+                            if i > 0 {
+                                plan.needles.push(Needle { x: corner.0, y: corner.1, command: Command::Stitch });
+                            } else if position != corner {
+                                plan.needles.push(Needle { x: corner.0, y: corner.1, command: Command::Jump });
+                            }
+                            position = corner;
+                        }
+                    }
+                }
+            }
+        }
+
+        plan.needles.push(Needle { x: position.0, y: position.1, command: Command::End });
+        plan
+    }
+
+    pub fn stitch_count(&self) -> usize {
+        self.needles.iter().filter(|needle| needle.command == Command::Stitch).count()
+    }
+
+    pub fn jump_count(&self) -> usize {
+        self.needles.iter().filter(|needle| needle.command == Command::Jump).count()
+    }
+
+    /// Total distance moved without sewing, in millimeters
+    pub fn jump_length(&self) -> f32 {
+        let mut length = 0.0;
+        let mut previous = (0, 0);
+        for needle in &self.needles {
+            if needle.command == Command::Jump {
+                length += (((needle.x - previous.0).pow(2) + (needle.y - previous.1).pow(2)) as f32).sqrt();
+            }
+            previous = (needle.x, needle.y);
+        }
+
+        length / UNITS_PER_MM
+    }
+
+    /// Leftmost, topmost, rightmost and bottommost needle positions
+    fn extents(&self) -> (i32, i32, i32, i32) {
+        self.needles.iter().fold((0, 0, 0, 0), |(left, top, right, bottom), needle| {
+            (left.min(needle.x), top.min(needle.y), right.max(needle.x), bottom.max(needle.y))
+        })
+    }
+
+    /// Draws the needle path, with stitches in their thread colors and jumps as thin red lines.
+    pub fn preview(&self) -> ColorImage {
+        let (left, top, right, bottom) = self.extents();
+        let scale = PREVIEW_SIZE / (right - left).max(bottom - top).max(1) as f32;
+        let size = [((right - left) as f32 * scale) as usize + 1, ((bottom - top) as f32 * scale) as usize + 1];
+        let mut image = ColorImage::new(size, Color32::WHITE);
+        let to_pixel = |x: i32, y: i32| ((x - left) as f32 * scale, (y - top) as f32 * scale);
+
+        let jump_color = Color32::from_rgb(230, 40, 40);
+        let mut color = 0;
+        let mut previous = to_pixel(0, 0);
+        for needle in &self.needles {
+            let next = to_pixel(needle.x, needle.y);
+            match needle.command {
+                Command::Stitch => draw_line(&mut image, previous, next, self.colors[color]),
+                Command::Jump => draw_line(&mut image, previous, next, jump_color),
+                Command::ColorChange => color += 1,
+                Command::End => {},
+            }
+            previous = next;
+        }
+
+        image
+    }
+
+    /// Each needle position as a move from the one before, with long moves split into jumps short enough for `max_move`.
+    /// The design starts with a jump from its middle to the first needle position.
+    fn moves(&self, max_move: i32) -> Vec<(i32, i32, Command)> {
+        let mut moves = vec![];
+        let mut previous = (0, 0);
+        for needle in &self.needles {
+            let (dx, dy) = (needle.x - previous.0, needle.y - previous.1);
+            let steps = (dx.abs().max(dy.abs()) + max_move - 1) / max_move;
+            let mut moved = (0, 0);
+            for step in 1..steps {
+                let (x, y) = (dx * step / steps, dy * step / steps);
+                moves.push((x - moved.0, y - moved.1, Command::Jump));
+                moved = (x, y);
+            }
+            moves.push((dx - moved.0, dy - moved.1, needle.command));
+            previous = (needle.x, needle.y);
+        }
+
+        moves
+    }
+
+    /// Tajima DST: a text header, then three bytes per move in balanced ternary.
+    pub fn to_dst(&self, name: &str) -> Vec<u8> {
+        let moves = self.moves(DST_MAX_MOVE);
+        let (left, top, right, bottom) = self.extents();
+        let end = self.needles.last().map_or((0, 0), |needle| (needle.x, needle.y));
+        // Needle y grows downwards, but DST's y axis points up.
+        let signed = |value: i32| format!("{}{:5}", if value < 0 { '-' } else { '+' }, value.abs());
+
+        let mut header = String::new();
+        header.push_str(&format!("LA:{:<16}\r", name.chars().take(16).collect::<String>()));
+        header.push_str(&format!("ST:{:7}\r", moves.len()));
+        header.push_str(&format!("CO:{:3}\r", self.colors.len().saturating_sub(1)));
+        header.push_str(&format!("+X:{:5}\r-X:{:5}\r+Y:{:5}\r-Y:{:5}\r", right, -left, -top, bottom));
+        header.push_str(&format!("AX:{}\rAY:{}\r", signed(end.0), signed(-end.1)));
+        header.push_str("MX:+    0\rMY:+    0\rPD:******\r");
+
+        let mut bytes = header.into_bytes();
+        bytes.push(0x1A);
+        bytes.resize(DST_HEADER_SIZE, b' ');
+
+        for (dx, dy, command) in moves {
+            bytes.extend_from_slice(&dst_record(dx, dy, command));
+        }
+        bytes
+    }
+
+    /// Brother PES: a minimal PES header pointing at the PEC section machines actually sew from.
+    pub fn to_pes(&self, name: &str) -> Vec<u8> {
+        // Version 1 header, with the PEC section starting right after it
+        let mut bytes = b"#PES0001".to_vec();
+        bytes.extend_from_slice(&22u32.to_le_bytes());
+        bytes.resize(22, 0);
+
+        // PEC header: label, thread colors (from the machine's fixed palette), padded to 512 bytes
+        bytes.extend_from_slice(format!("LA:{:<16}\r", name.chars().take(16).collect::<String>()).as_bytes());
+        bytes.extend_from_slice(&[b' '; 12]);
+        bytes.extend_from_slice(&[0xFF, 0x00, (PEC_ICON_WIDTH / 8) as u8, PEC_ICON_HEIGHT as u8]);
+        bytes.extend_from_slice(&[b' '; 12]);
+        bytes.push(self.colors.len().saturating_sub(1) as u8);
+        bytes.extend(self.colors.iter().map(|color| closest_pec_thread(*color)));
+        bytes.resize(22 + 512, b' ');
+
+        // Stitch block, its length filled in once it's written
+        let block_start = bytes.len();
+        let (left, top, right, bottom) = self.extents();
+        bytes.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x31, 0xFF, 0xF0]);
+        bytes.extend_from_slice(&((right - left) as u16).to_le_bytes());
+        bytes.extend_from_slice(&((bottom - top) as u16).to_le_bytes());
+        bytes.extend_from_slice(&0x1E0u16.to_le_bytes());
+        bytes.extend_from_slice(&0x1B0u16.to_le_bytes());
+        bytes.extend_from_slice(&(0x9000 | (-left as u16 & 0x0FFF)).to_be_bytes());
+        bytes.extend_from_slice(&(0x9000 | (-top as u16 & 0x0FFF)).to_be_bytes());
+
+        let mut second_color = true;
+        for (dx, dy, command) in self.moves(PEC_MAX_MOVE) {
+            match command {
+                Command::Stitch if (-64..64).contains(&dx) && (-64..64).contains(&dy) => {
+                    bytes.extend_from_slice(&[dx as u8 & 0x7F, dy as u8 & 0x7F]);
+                },
+                Command::Stitch => {
+                    bytes.extend_from_slice(&pec_long_move(dx, 0));
+                    bytes.extend_from_slice(&pec_long_move(dy, 0));
+                },
+                Command::Jump => {
+                    bytes.extend_from_slice(&pec_long_move(dx, PEC_JUMP));
+                    bytes.extend_from_slice(&pec_long_move(dy, PEC_JUMP));
+                },
+                Command::ColorChange => {
+                    bytes.extend_from_slice(&[0xFE, 0xB0, if second_color { 2 } else { 1 }]);
+                    second_color = !second_color;
+                },
+                Command::End => bytes.push(0xFF),
+            }
+        }
+        let block_length = (bytes.len() - block_start) as u32;
+        bytes[block_start + 2..block_start + 5].copy_from_slice(&block_length.to_le_bytes()[..3]);
+
+        // Thumbnails: the whole design, then each color on its own
+        bytes.extend(self.pec_icon(None));
+        for color in 0..self.colors.len() {
+            bytes.extend(self.pec_icon(Some(color)));
+        }
+
+        bytes
+    }
+
+    /// One-bit thumbnail of the stitches of one color block, or all of them, inside a frame.
+    fn pec_icon(&self, only_color: Option<usize>) -> Vec<u8> {
+        let mut icon = vec![0u8; PEC_ICON_WIDTH / 8 * PEC_ICON_HEIGHT];
+        let mut set = |x: usize, y: usize| icon[x / 8 + y * (PEC_ICON_WIDTH / 8)] |= 1 << (x % 8);
+        for x in 0..PEC_ICON_WIDTH {
+            set(x, 0);
+            set(x, PEC_ICON_HEIGHT - 1);
+        }
+        for y in 0..PEC_ICON_HEIGHT {
+            set(0, y);
+            set(PEC_ICON_WIDTH - 1, y);
+        }
+
+        let (left, top, right, bottom) = self.extents();
+        let scale = ((PEC_ICON_WIDTH - 8) as f32 / (right - left).max(1) as f32).min((PEC_ICON_HEIGHT - 8) as f32 / (bottom - top).max(1) as f32);
+        let mut color = 0;
+        for needle in &self.needles {
+            match needle.command {
+                Command::ColorChange => color += 1,
+                Command::Stitch if only_color.unwrap_or(color) == color => {
+                    set(4 + ((needle.x - left) as f32 * scale) as usize, 4 + ((needle.y - top) as f32 * scale) as usize);
+                },
+                _ => {},
+            }
+        }
+
+        icon
+    }
+}
+
+/// Sets pixels along a line, thin and without antialiasing.
+fn draw_line(image: &mut ColorImage, from: (f32, f32), to: (f32, f32), color: Color32) {
+    let steps = (to.0 - from.0).abs().max((to.1 - from.1).abs()).ceil().max(1.0) as usize;
+    for step in 0..=steps {
+        let t = step as f32 / steps as f32;
+        let (x, y) = ((from.0 + (to.0 - from.0) * t) as usize, (from.1 + (to.1 - from.1) * t) as usize);
+        if x < image.size[0] && y < image.size[1] {
+            image.pixels[x + y * image.size[0]] = color;
+        }
+    }
+}
+
+/// One DST move, with each axis split into trits of 1, 3, 9, 27 and 81 units. DST's y axis points up.
+fn dst_record(dx: i32, dy: i32, command: Command) -> [u8; 3] {
+    if command == Command::End {
+        return [0x00, 0x00, 0xF3];
+    }
+
+    // (byte, bit for +, bit for -) of each trit, largest first
+    const X_TRITS: [(usize, u8, u8, i32); 5] = [(2, 0x04, 0x08, 81), (1, 0x04, 0x08, 27), (0, 0x04, 0x08, 9), (1, 0x01, 0x02, 3), (0, 0x01, 0x02, 1)];
+    const Y_TRITS: [(usize, u8, u8, i32); 5] = [(2, 0x20, 0x10, 81), (1, 0x20, 0x10, 27), (0, 0x20, 0x10, 9), (1, 0x80, 0x40, 3), (0, 0x80, 0x40, 1)];
+
+    let mut record = [0u8, 0u8, 0x03];
+    for (mut value, trits) in [(dx, X_TRITS), (-dy, Y_TRITS)] {
+        for (byte, plus, minus, weight) in trits {
+            // Values up to half the weight below are left for the smaller trits.
+            if value > weight / 2 {
+                record[byte] |= plus;
+                value -= weight;
+            } else if value < -(weight / 2) {
+                record[byte] |= minus;
+                value += weight;
+            }
+        }
+    }
+
+    match command {
+        Command::Jump => record[2] |= 0x80,
+        Command::ColorChange => record[2] |= 0xC0,
+        _ => {},
+    }
+    record
+}
+
+// PEC long moves hold 12 bits, flagged as a jump or a stitch
+const PEC_MAX_MOVE: i32 = 2047;
+const PEC_JUMP: u16 = 0x1000;
+
+fn pec_long_move(value: i32, flags: u16) -> [u8; 2] {
+    (0x8000 | flags | (value as u16 & 0x0FFF)).to_be_bytes()
+}
+
+const PEC_ICON_WIDTH: usize = 48;
+const PEC_ICON_HEIGHT: usize = 38;
+
+/// Index (from 1) of the closest thread in the palette every PEC file refers to by number.
+fn closest_pec_thread(color: Color32) -> u8 {
+    let lab = Lab::from(Rgba::from(color));
+    let distance = |rgb: &[u8; 3]| lab.delta_e(&Lab::from(Rgba::from(Color32::from_rgb(rgb[0], rgb[1], rgb[2]))));
+    PEC_THREADS.iter().enumerate()
+        .min_by(|(_, a), (_, b)| distance(a).total_cmp(&distance(b)))
+        .map_or(1, |(i, _)| i as u8 + 1)
+}
+
+// Brother's fixed PEC thread palette, leaving out the three applique markers at the end
+const PEC_THREADS: &[[u8; 3]] = &[
+    [14, 31, 124], [10, 85, 163], [48, 135, 119], [75, 107, 175], [237, 23, 31], [209, 92, 0], [145, 54, 151], [228, 154, 203],
+    [145, 95, 172], [158, 214, 125], [232, 169, 0], [254, 186, 53], [255, 255, 0], [112, 188, 31], [186, 152, 0], [168, 168, 168],
+    [125, 111, 0], [255, 255, 179], [79, 85, 86], [0, 0, 0], [11, 61, 145], [119, 1, 118], [41, 49, 51], [42, 19, 1],
+    [246, 74, 138], [178, 118, 36], [252, 187, 197], [254, 55, 15], [240, 240, 240], [106, 28, 138], [168, 221, 196], [37, 132, 187],
+    [254, 179, 67], [255, 243, 107], [208, 166, 96], [209, 84, 0], [102, 186, 73], [19, 74, 70], [135, 135, 135], [216, 204, 198],
+    [67, 86, 7], [253, 217, 222], [249, 147, 188], [0, 56, 34], [178, 175, 212], [104, 106, 176], [239, 227, 185], [247, 56, 102],
+    [181, 75, 100], [19, 43, 26], [199, 1, 86], [254, 158, 50], [168, 222, 235], [0, 103, 62], [78, 41, 144], [47, 126, 32],
+    [255, 204, 204], [255, 217, 17], [9, 91, 166], [240, 249, 112], [227, 243, 91],
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::egui::Color32;
+    use crate::cross::craft::Geometry;
+    use crate::cross::floss::{Catalog, DMC};
+    use crate::cross::renderer::PaletteColor;
+
+    /// Adds up the trits of a DST record, back into a move with y growing downwards.
+    fn decode_dst(record: [u8; 3]) -> (i32, i32, Command) {
+        if record == [0x00, 0x00, 0xF3] {
+            return (0, 0, Command::End);
+        }
+
+        let trit = |byte: usize, plus: u8, minus: u8, weight: i32| {
+            if record[byte] & plus != 0 { weight } else if record[byte] & minus != 0 { -weight } else { 0 }
+        };
+        let dx = trit(2, 0x04, 0x08, 81) + trit(1, 0x04, 0x08, 27) + trit(0, 0x04, 0x08, 9) + trit(1, 0x01, 0x02, 3) + trit(0, 0x01, 0x02, 1);
+        let dy = trit(2, 0x20, 0x10, 81) + trit(1, 0x20, 0x10, 27) + trit(0, 0x20, 0x10, 9) + trit(1, 0x80, 0x40, 3) + trit(0, 0x80, 0x40, 1);
+        let command = match record[2] & 0xC0 {
+            0xC0 => Command::ColorChange,
+            0x80 => Command::Jump,
+            _ => Command::Stitch,
+        };
+        (dx, -dy, command)
+    }
+
+    /// Reads a PEC stitch block's moves, up to and including the end.
+    fn decode_pec(mut bytes: &[u8]) -> Vec<(i32, i32, Command)> {
+        let mut moves = vec![];
+        loop {
+            match bytes {
+                [0xFF, ..] => {
+                    moves.push((0, 0, Command::End));
+                    return moves;
+                },
+                [0xFE, 0xB0, _, rest @ ..] => {
+                    moves.push((0, 0, Command::ColorChange));
+                    bytes = rest;
+                },
+                _ => {
+                    let mut jump = false;
+                    let mut axis = || {
+                        if bytes[0] & 0x80 != 0 {
+                            jump |= bytes[0] & 0x10 != 0;
+                            let value = (i32::from(bytes[0] & 0x0F) << 8 | i32::from(bytes[1])) << 20 >> 20;
+                            bytes = &bytes[2..];
+                            value
+                        } else {
+                            let value = i32::from(bytes[0]) << 25 >> 25;
+                            bytes = &bytes[1..];
+                            value
+                        }
+                    };
+                    let (dx, dy) = (axis(), axis());
+                    moves.push((dx, dy, if jump { Command::Jump } else { Command::Stitch }));
+                },
+            }
+        }
+    }
+
+    fn chart(width: usize, height: usize, stitches: Vec<usize>) -> ChartData {
+        let palette = vec![PaletteColor::new(DMC[0].color(), &DMC[0]), PaletteColor::new(DMC[1].color(), &DMC[1])];
+        ChartData { width, height, geometry: Geometry::default(), catalog: Catalog::Dmc, palette, stitches, backstitches: vec![] }
+    }
+
+    /// A plan with short and long stitches, a jump too long for one move, and a color change.
+    fn sample_plan() -> StitchPlan {
+        let needles = [(-3000, -200, Command::Jump), (-2990, -150, Command::Stitch), (-2800, 100, Command::Stitch),
+            (-2800, 100, Command::ColorChange), (2500, 900, Command::Jump), (2440, 961, Command::Stitch), (2440, 961, Command::End)];
+        StitchPlan {
+            needles: needles.iter().map(|(x, y, command)| Needle { x: *x, y: *y, command: *command }).collect(),
+            colors: vec![Color32::RED, Color32::BLUE],
+        }
+    }
+
+    #[test]
+    fn dst_records_decode_to_their_moves() {
+        for dx in -DST_MAX_MOVE..=DST_MAX_MOVE {
+            for dy in [-DST_MAX_MOVE, -40, -1, 0, 1, 13, DST_MAX_MOVE] {
+                for command in [Command::Stitch, Command::Jump, Command::ColorChange] {
+                    assert!(decode_dst(dst_record(dx, dy, command)) == (dx, dy, command), "({}, {})", dx, dy);
+                }
+            }
+        }
+        assert!(decode_dst(dst_record(0, 0, Command::End)) == (0, 0, Command::End));
+    }
+
+    #[test]
+    fn dst_points_y_up() {
+        assert_eq!(dst_record(0, -1, Command::Stitch), [0x80, 0x00, 0x03]);
+
+        let dst = sample_plan().to_dst("sample");
+        let header = String::from_utf8_lossy(&dst[..DST_HEADER_SIZE]).to_string();
+        assert!(header.contains("+Y:  200\r-Y:  961\r"), "{}", header);
+        assert!(header.contains("AX:+ 2440\rAY:-  961\r"), "{}", header);
+    }
+
+    /// Follows the moves from the middle of the design, checking they pass through every needle position in order.
+    fn assert_reaches_needles(plan: &StitchPlan, moves: &[(i32, i32, Command)]) {
+        let mut position = (0, 0);
+        let mut needles = plan.needles.iter().peekable();
+        for (dx, dy, command) in moves {
+            position = (position.0 + dx, position.1 + dy);
+            if needles.next_if(|needle| (needle.x, needle.y, needle.command) == (position.0, position.1, *command)).is_none() {
+                assert!(*command == Command::Jump, "{:?} isn't a needle position", position);
+            }
+        }
+        assert!(needles.next().is_none(), "not every needle position was reached");
+    }
+
+    #[test]
+    fn dst_moves_reach_the_needles() {
+        let plan = sample_plan();
+        let dst = plan.to_dst("sample");
+        let moves: Vec<(i32, i32, Command)> = dst[DST_HEADER_SIZE..].chunks(3).map(|record| decode_dst([record[0], record[1], record[2]])).collect();
+        assert_reaches_needles(&plan, &moves);
+    }
+
+    #[test]
+    fn pec_block_decodes_to_the_needles() {
+        let plan = sample_plan();
+        let pes = plan.to_pes("sample");
+        let block = &pes[22 + 512..];
+        let block_length = u32::from_le_bytes([block[2], block[3], block[4], 0]) as usize;
+        assert_eq!(block[block_length - 1], 0xFF);
+
+        // The moves follow the block header, the design size and the offset of its top left corner.
+        assert_eq!(u16::from_be_bytes([block[16], block[17]]) & 0xF000, 0x9000);
+        assert_eq!(u16::from_be_bytes([block[18], block[19]]) & 0xF000, 0x9000);
+        assert_reaches_needles(&plan, &decode_pec(&block[20..block_length]));
+    }
+
+    #[test]
+    fn cells_are_sewn_as_crosses_without_edges() {
+        let chart_data = chart(5, 2, vec![0, 0, 1, 0, 0, 0, 1, 1, 1, 0]);
+        let plan = StitchPlan::new(&chart_data, [1.0, 1.0]);
+        let cell = (UNITS_PER_MM as i32, UNITS_PER_MM as i32);
+
+        let mut sewn = vec![];
+        for pair in plan.needles.windows(2) {
+            if pair[1].command == Command::Stitch {
+                let (from, to) = ((pair[0].x, pair[0].y), (pair[1].x, pair[1].y));
+                assert!((to.0 - from.0).abs() == cell.0 && (to.1 - from.1).abs() == cell.1, "{:?} to {:?} isn't a diagonal", from, to);
+                sewn.push((from.min(to), from.max(to)));
+            }
+        }
+
+        assert_eq!(sewn.len(), 2 * chart_data.stitches.len());
+        for (stitch, _) in chart_data.stitches.iter().enumerate() {
+            let (x, y) = (((stitch % 5) as i32 - 2) * cell.0 - cell.0 / 2, (stitch / 5) as i32 * cell.1 - cell.1);
+            assert!(sewn.contains(&((x, y), (x + cell.0, y + cell.1))), "no \\ in cell {}", stitch);
+            assert!(sewn.contains(&((x, y + cell.1), (x + cell.0, y))), "no / in cell {}", stitch);
+        }
+    }
+}
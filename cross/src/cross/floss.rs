@@ -0,0 +1,529 @@
+//! DMC six-strand cotton catalog, used to name chart colors and pick threads to buy.
+//! Crafts that aren't stitched in floss pick from their own catalog of the same shape.
+use std::fmt;
+
+use crate::egui::{Color32, Rgba};
+
+use super::color::Lab;
+use super::delica::DELICA;
+use super::yarn::YARN;
+
+pub struct Floss {
+    // Who makes it, as printed before the code
+    pub brand: &'static str,
+    pub code: &'static str,
+    pub name: &'static str,
+    pub rgb: [u8; 3],
+}
+
+impl Floss {
+    const fn new(code: &'static str, name: &'static str, r: u8, g: u8, b: u8) -> Self {
+        Floss { brand: "DMC", code, name, rgb: [r, g, b] }
+    }
+
+    pub fn color(&self) -> Color32 {
+        Color32::from_rgb(self.rgb[0], self.rgb[1], self.rgb[2])
+    }
+
+    pub fn lab(&self) -> Lab {
+        Lab::from(self.color())
+    }
+}
+
+/// Color of one strand each of two flosses in the needle, seen from a normal viewing distance
+pub fn blend_color(a: &Floss, b: &Floss) -> Color32 {
+    Color32::from((Rgba::from(a.color()) + Rgba::from(b.color())) * 0.5)
+}
+
+impl fmt::Display for Floss {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Colors that go by their name, such as DMC White, aren't named twice.
+        if self.code == self.name {
+            write!(f, "{} {}", self.brand, self.name)
+        } else {
+            write!(f, "{} {} {}", self.brand, self.code, self.name)
+        }
+    }
+}
+
+/// The range of materials a chart's colors are matched to and named after
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Catalog {
+    Dmc,
+    Delica,
+    Yarn,
+}
+
+impl Catalog {
+    pub fn flosses(self) -> &'static [Floss] {
+        match self {
+            Catalog::Dmc => DMC,
+            Catalog::Delica => DELICA,
+            Catalog::Yarn => YARN,
+        }
+    }
+
+    /// What the catalog's codes are called, as in "DMC code"
+    pub fn code_name(self) -> &'static str {
+        match self {
+            Catalog::Dmc => "DMC code",
+            Catalog::Delica => "Delica code",
+            Catalog::Yarn => "Yarn color",
+        }
+    }
+
+    pub fn find(self, code: &str) -> Option<&'static Floss> {
+        self.flosses().iter().find(|floss| floss.code == code)
+    }
+
+    /// Finds the catalog entry that looks closest to the given color
+    pub fn closest(self, color: Color32) -> &'static Floss {
+        closest_of(self.flosses(), color).expect("catalogs should never be empty")
+    }
+}
+
+/// Finds the floss that looks closest to the given color among some flosses, such as the stash
+pub fn closest_of(flosses: impl IntoIterator<Item = &'static Floss>, color: Color32) -> Option<&'static Floss> {
+    let target = Lab::from(color);
+    flosses.into_iter().min_by(|a, b| target.delta_e(&a.lab()).total_cmp(&target.delta_e(&b.lab())))
+}
+
+// A skein is 8m of six-strand floss. Stitching with two strands on 14-count fabric,
+// one skein covers roughly 1800 full cross stitches.
+pub const SKEIN_METERS: f32 = 8.0;
+const STITCHES_PER_SKEIN: f32 = 1800.0;
+
+/// Estimated length of (six-strand) floss needed for the given number of full cross stitches
+pub fn meters_needed(stitches: f32) -> f32 {
+    stitches * SKEIN_METERS / STITCHES_PER_SKEIN
+}
+
+// RGB approximations of the physical threads, as commonly published for chart software.
+pub const DMC: &[Floss] = &[
+    Floss::new("B5200", "Snow White", 255, 255, 255),
+    Floss::new("White", "White", 252, 251, 248),
+    Floss::new("Ecru", "Ecru", 240, 234, 218),
+    Floss::new("150", "Dusty Rose Ultra Very Dark", 171, 2, 73),
+    Floss::new("151", "Dusty Rose Very Light", 240, 206, 212),
+    Floss::new("152", "Shell Pink Medium Light", 226, 160, 153),
+    Floss::new("153", "Violet Very Light", 230, 204, 217),
+    Floss::new("154", "Grape Very Dark", 87, 36, 51),
+    Floss::new("155", "Blue Violet Medium Dark", 152, 145, 182),
+    Floss::new("156", "Blue Violet Medium Light", 163, 174, 209),
+    Floss::new("157", "Cornflower Blue Very Light", 187, 195, 217),
+    Floss::new("158", "Cornflower Blue Medium Very Dark", 76, 82, 110),
+    Floss::new("159", "Blue Gray Light", 199, 202, 215),
+    Floss::new("160", "Blue Gray Medium", 153, 159, 183),
+    Floss::new("161", "Blue Gray", 120, 128, 164),
+    Floss::new("162", "Blue Ultra Very Light", 219, 236, 245),
+    Floss::new("163", "Celadon Green Medium", 77, 131, 97),
+    Floss::new("164", "Forest Green Light", 200, 216, 184),
+    Floss::new("165", "Moss Green Very Light", 239, 244, 164),
+    Floss::new("166", "Moss Green Medium Light", 192, 200, 64),
+    Floss::new("167", "Yellow Beige Very Dark", 167, 124, 73),
+    Floss::new("168", "Pewter Very Light", 209, 209, 209),
+    Floss::new("169", "Pewter Light", 132, 132, 132),
+    Floss::new("208", "Lavender Very Dark", 131, 91, 139),
+    Floss::new("209", "Lavender Dark", 163, 123, 167),
+    Floss::new("210", "Lavender Medium", 195, 159, 195),
+    Floss::new("211", "Lavender Light", 227, 203, 227),
+    Floss::new("221", "Shell Pink Very Dark", 136, 62, 67),
+    Floss::new("223", "Shell Pink Light", 204, 132, 124),
+    Floss::new("224", "Shell Pink Very Light", 235, 183, 175),
+    Floss::new("225", "Shell Pink Ultra Very Light", 255, 223, 213),
+    Floss::new("300", "Mahogany Very Dark", 111, 47, 0),
+    Floss::new("301", "Mahogany Medium", 179, 95, 43),
+    Floss::new("304", "Red Medium", 183, 31, 51),
+    Floss::new("307", "Lemon", 253, 237, 84),
+    Floss::new("309", "Rose Dark", 186, 74, 74),
+    Floss::new("310", "Black", 0, 0, 0),
+    Floss::new("311", "Navy Blue Medium", 28, 80, 102),
+    Floss::new("312", "Baby Blue Very Dark", 53, 82, 102),
+    Floss::new("315", "Antique Mauve Medium Dark", 129, 73, 82),
+    Floss::new("316", "Antique Mauve Medium", 183, 115, 127),
+    Floss::new("317", "Pewter Gray", 108, 108, 108),
+    Floss::new("318", "Steel Gray Light", 171, 171, 171),
+    Floss::new("319", "Pistachio Green Very Dark", 32, 95, 46),
+    Floss::new("320", "Pistachio Green Medium", 105, 136, 90),
+    Floss::new("321", "Red", 199, 43, 59),
+    Floss::new("322", "Baby Blue Dark", 90, 143, 184),
+    Floss::new("326", "Rose Very Dark", 179, 59, 75),
+    Floss::new("327", "Violet Dark", 99, 54, 102),
+    Floss::new("333", "Blue Violet Very Dark", 92, 84, 120),
+    Floss::new("334", "Baby Blue Medium", 115, 159, 193),
+    Floss::new("335", "Rose", 238, 84, 110),
+    Floss::new("336", "Navy Blue", 37, 59, 115),
+    Floss::new("340", "Blue Violet Medium", 173, 167, 199),
+    Floss::new("341", "Blue Violet Light", 183, 191, 221),
+    Floss::new("347", "Salmon Very Dark", 191, 45, 45),
+    Floss::new("349", "Coral Dark", 210, 16, 53),
+    Floss::new("350", "Coral Medium", 224, 72, 72),
+    Floss::new("351", "Coral", 233, 106, 103),
+    Floss::new("352", "Coral Light", 253, 156, 151),
+    Floss::new("353", "Peach", 254, 215, 204),
+    Floss::new("355", "Terra Cotta Dark", 152, 68, 54),
+    Floss::new("356", "Terra Cotta Medium", 197, 106, 91),
+    Floss::new("367", "Pistachio Green Dark", 97, 122, 82),
+    Floss::new("368", "Pistachio Green Light", 166, 194, 152),
+    Floss::new("369", "Pistachio Green Very Light", 215, 237, 204),
+    Floss::new("370", "Mustard Medium", 184, 157, 100),
+    Floss::new("371", "Mustard", 191, 166, 113),
+    Floss::new("372", "Mustard Light", 204, 183, 132),
+    Floss::new("400", "Mahogany Dark", 143, 67, 15),
+    Floss::new("402", "Mahogany Very Light", 247, 167, 119),
+    Floss::new("407", "Desert Sand Dark", 187, 129, 97),
+    Floss::new("413", "Pewter Gray Dark", 86, 86, 86),
+    Floss::new("414", "Steel Gray Dark", 140, 140, 140),
+    Floss::new("415", "Pearl Gray", 211, 211, 214),
+    Floss::new("420", "Hazelnut Brown Dark", 160, 112, 66),
+    Floss::new("422", "Hazelnut Brown Light", 198, 159, 123),
+    Floss::new("433", "Brown Medium", 122, 69, 31),
+    Floss::new("434", "Brown Light", 152, 94, 51),
+    Floss::new("435", "Brown Very Light", 184, 119, 72),
+    Floss::new("436", "Tan", 203, 144, 81),
+    Floss::new("437", "Tan Light", 228, 187, 142),
+    Floss::new("444", "Lemon Dark", 255, 214, 0),
+    Floss::new("445", "Lemon Light", 255, 251, 139),
+    Floss::new("498", "Red Dark", 167, 19, 43),
+    Floss::new("500", "Blue Green Very Dark", 4, 77, 51),
+    Floss::new("501", "Blue Green Dark", 57, 111, 82),
+    Floss::new("502", "Blue Green", 91, 144, 113),
+    Floss::new("503", "Blue Green Medium", 123, 172, 148),
+    Floss::new("504", "Blue Green Very Light", 196, 222, 204),
+    Floss::new("517", "Wedgewood Dark", 59, 118, 143),
+    Floss::new("518", "Wedgewood Light", 79, 147, 167),
+    Floss::new("519", "Sky Blue", 126, 177, 200),
+    Floss::new("520", "Fern Green Dark", 102, 109, 79),
+    Floss::new("522", "Fern Green", 150, 158, 126),
+    Floss::new("523", "Fern Green Light", 171, 177, 151),
+    Floss::new("524", "Fern Green Very Light", 196, 205, 172),
+    Floss::new("535", "Ash Gray Very Light", 99, 100, 88),
+    Floss::new("543", "Beige Brown Ultra Very Light", 242, 227, 206),
+    Floss::new("550", "Violet Very Dark", 92, 24, 78),
+    Floss::new("552", "Violet Medium", 128, 58, 107),
+    Floss::new("553", "Violet", 163, 99, 139),
+    Floss::new("554", "Violet Light", 219, 179, 203),
+    Floss::new("561", "Jade Very Dark", 44, 106, 69),
+    Floss::new("562", "Jade Medium", 83, 151, 106),
+    Floss::new("563", "Jade Light", 143, 192, 152),
+    Floss::new("564", "Jade Very Light", 167, 205, 175),
+    Floss::new("580", "Moss Green Dark", 136, 141, 51),
+    Floss::new("581", "Moss Green", 167, 174, 56),
+    Floss::new("597", "Turquoise", 91, 163, 179),
+    Floss::new("598", "Turquoise Light", 144, 195, 204),
+    Floss::new("600", "Cranberry Very Dark", 205, 47, 99),
+    Floss::new("601", "Cranberry Dark", 209, 40, 106),
+    Floss::new("602", "Cranberry Medium", 226, 72, 116),
+    Floss::new("603", "Cranberry", 255, 164, 190),
+    Floss::new("604", "Cranberry Light", 255, 176, 190),
+    Floss::new("605", "Cranberry Very Light", 255, 192, 205),
+    Floss::new("606", "Bright Orange-Red", 250, 50, 3),
+    Floss::new("608", "Bright Orange", 253, 93, 53),
+    Floss::new("610", "Drab Brown Dark", 121, 96, 71),
+    Floss::new("611", "Drab Brown", 150, 118, 86),
+    Floss::new("612", "Drab Brown Light", 188, 154, 120),
+    Floss::new("613", "Drab Brown Very Light", 220, 196, 170),
+    Floss::new("632", "Desert Sand Ultra Very Dark", 135, 85, 57),
+    Floss::new("640", "Beige Gray Very Dark", 133, 123, 97),
+    Floss::new("642", "Beige Gray Dark", 164, 152, 120),
+    Floss::new("644", "Beige Gray Medium", 221, 216, 203),
+    Floss::new("645", "Beaver Gray Very Dark", 110, 101, 92),
+    Floss::new("646", "Beaver Gray Dark", 135, 125, 115),
+    Floss::new("647", "Beaver Gray Medium", 176, 166, 156),
+    Floss::new("648", "Beaver Gray Light", 188, 180, 172),
+    Floss::new("666", "Bright Red", 227, 29, 66),
+    Floss::new("676", "Old Gold Light", 229, 206, 151),
+    Floss::new("677", "Old Gold Very Light", 245, 236, 203),
+    Floss::new("680", "Old Gold Dark", 188, 141, 14),
+    Floss::new("699", "Green", 5, 101, 23),
+    Floss::new("700", "Green Bright", 7, 115, 27),
+    Floss::new("701", "Green Light", 63, 143, 41),
+    Floss::new("702", "Kelly Green", 71, 167, 47),
+    Floss::new("703", "Chartreuse", 123, 181, 71),
+    Floss::new("704", "Chartreuse Bright", 158, 207, 52),
+    Floss::new("712", "Cream", 255, 251, 239),
+    Floss::new("718", "Plum", 156, 36, 98),
+    Floss::new("720", "Orange Spice Dark", 229, 92, 31),
+    Floss::new("721", "Orange Spice Medium", 242, 120, 66),
+    Floss::new("722", "Orange Spice Light", 247, 151, 111),
+    Floss::new("725", "Topaz Medium Light", 255, 200, 64),
+    Floss::new("726", "Topaz Light", 253, 215, 85),
+    Floss::new("727", "Topaz Very Light", 255, 241, 175),
+    Floss::new("728", "Topaz", 228, 180, 104),
+    Floss::new("729", "Old Gold Medium", 208, 165, 62),
+    Floss::new("730", "Olive Green Very Dark", 130, 123, 48),
+    Floss::new("732", "Olive Green", 148, 140, 54),
+    Floss::new("733", "Olive Green Medium", 188, 179, 76),
+    Floss::new("734", "Olive Green Light", 199, 192, 119),
+    Floss::new("738", "Tan Very Light", 236, 204, 158),
+    Floss::new("739", "Tan Ultra Very Light", 248, 228, 200),
+    Floss::new("740", "Tangerine", 255, 139, 0),
+    Floss::new("741", "Tangerine Medium", 255, 163, 43),
+    Floss::new("742", "Tangerine Light", 255, 191, 87),
+    Floss::new("743", "Yellow Medium", 254, 211, 118),
+    Floss::new("744", "Yellow Pale", 255, 231, 147),
+    Floss::new("745", "Yellow Light Pale", 255, 233, 173),
+    Floss::new("746", "Off White", 252, 252, 238),
+    Floss::new("747", "Peacock Blue Very Light", 229, 252, 253),
+    Floss::new("754", "Peach Light", 247, 203, 191),
+    Floss::new("758", "Terra Cotta Very Light", 238, 170, 155),
+    Floss::new("760", "Salmon", 245, 173, 173),
+    Floss::new("761", "Salmon Light", 255, 201, 201),
+    Floss::new("762", "Pearl Gray Very Light", 236, 236, 236),
+    Floss::new("772", "Yellow Green Very Light", 228, 236, 212),
+    Floss::new("775", "Baby Blue Very Light", 217, 235, 241),
+    Floss::new("776", "Pink Medium", 252, 176, 185),
+    Floss::new("777", "Raspberry Very Dark", 145, 53, 70),
+    Floss::new("780", "Topaz Ultra Very Dark", 148, 99, 26),
+    Floss::new("781", "Topaz Very Dark", 162, 109, 32),
+    Floss::new("782", "Topaz Dark", 174, 119, 32),
+    Floss::new("783", "Topaz Medium", 206, 145, 36),
+    Floss::new("791", "Cornflower Blue Very Dark", 70, 69, 99),
+    Floss::new("792", "Cornflower Blue Dark", 85, 91, 123),
+    Floss::new("793", "Cornflower Blue Medium", 112, 125, 162),
+    Floss::new("794", "Cornflower Blue Light", 143, 156, 193),
+    Floss::new("796", "Royal Blue Dark", 17, 65, 109),
+    Floss::new("797", "Royal Blue", 19, 71, 125),
+    Floss::new("798", "Delft Blue Dark", 70, 106, 142),
+    Floss::new("799", "Delft Blue Medium", 116, 142, 182),
+    Floss::new("800", "Delft Blue Pale", 192, 204, 222),
+    Floss::new("801", "Coffee Brown Dark", 101, 57, 25),
+    Floss::new("806", "Peacock Blue Dark", 61, 149, 165),
+    Floss::new("807", "Peacock Blue", 100, 171, 186),
+    Floss::new("809", "Delft Blue", 148, 168, 198),
+    Floss::new("813", "Blue Light", 161, 194, 215),
+    Floss::new("814", "Garnet Dark", 123, 0, 27),
+    Floss::new("815", "Garnet Medium", 135, 7, 31),
+    Floss::new("816", "Garnet", 151, 11, 35),
+    Floss::new("817", "Coral Red Very Dark", 187, 5, 31),
+    Floss::new("818", "Baby Pink", 255, 223, 217),
+    Floss::new("819", "Baby Pink Light", 255, 238, 235),
+    Floss::new("820", "Royal Blue Very Dark", 14, 54, 92),
+    Floss::new("822", "Beige Gray Light", 231, 226, 211),
+    Floss::new("823", "Navy Blue Dark", 33, 48, 99),
+    Floss::new("824", "Blue Very Dark", 57, 105, 135),
+    Floss::new("825", "Blue Dark", 71, 129, 165),
+    Floss::new("826", "Blue Medium", 107, 158, 191),
+    Floss::new("827", "Blue Very Light", 189, 221, 237),
+    Floss::new("828", "Sky Blue Very Light", 197, 232, 237),
+    Floss::new("829", "Golden Olive Very Dark", 126, 107, 66),
+    Floss::new("830", "Golden Olive Dark", 141, 120, 75),
+    Floss::new("831", "Golden Olive Medium", 170, 143, 86),
+    Floss::new("832", "Golden Olive", 189, 155, 81),
+    Floss::new("833", "Golden Olive Light", 200, 171, 108),
+    Floss::new("834", "Golden Olive Very Light", 219, 190, 127),
+    Floss::new("838", "Beige Brown Very Dark", 89, 73, 55),
+    Floss::new("839", "Beige Brown Dark", 103, 85, 65),
+    Floss::new("840", "Beige Brown Medium", 154, 124, 92),
+    Floss::new("841", "Beige Brown Light", 182, 155, 126),
+    Floss::new("842", "Beige Brown Very Light", 209, 186, 161),
+    Floss::new("844", "Beaver Brown Ultra Dark", 72, 72, 72),
+    Floss::new("869", "Hazelnut Brown Very Dark", 131, 94, 57),
+    Floss::new("890", "Pistachio Green Ultra Very Dark", 23, 73, 35),
+    Floss::new("891", "Carnation Dark", 255, 87, 115),
+    Floss::new("892", "Carnation Medium", 255, 121, 140),
+    Floss::new("893", "Carnation Light", 252, 144, 162),
+    Floss::new("894", "Carnation Very Light", 255, 178, 187),
+    Floss::new("895", "Hunter Green Very Dark", 27, 83, 0),
+    Floss::new("898", "Coffee Brown Very Dark", 73, 42, 19),
+    Floss::new("899", "Rose Medium", 242, 118, 136),
+    Floss::new("900", "Burnt Orange Dark", 209, 88, 7),
+    Floss::new("902", "Garnet Very Dark", 130, 38, 55),
+    Floss::new("904", "Parrot Green Very Dark", 85, 120, 34),
+    Floss::new("905", "Parrot Green Dark", 98, 138, 40),
+    Floss::new("906", "Parrot Green Medium", 127, 179, 53),
+    Floss::new("907", "Parrot Green Light", 199, 230, 102),
+    Floss::new("909", "Emerald Green Very Dark", 21, 111, 73),
+    Floss::new("910", "Emerald Green Dark", 24, 126, 86),
+    Floss::new("911", "Emerald Green Medium", 24, 144, 101),
+    Floss::new("912", "Emerald Green Light", 27, 157, 107),
+    Floss::new("913", "Nile Green Medium", 109, 171, 119),
+    Floss::new("915", "Plum Dark", 130, 0, 67),
+    Floss::new("917", "Plum Medium", 155, 19, 89),
+    Floss::new("918", "Red Copper Dark", 130, 52, 10),
+    Floss::new("919", "Red Copper", 166, 69, 16),
+    Floss::new("920", "Copper Medium", 172, 84, 20),
+    Floss::new("921", "Copper", 198, 98, 24),
+    Floss::new("922", "Copper Light", 226, 115, 35),
+    Floss::new("924", "Gray Green Very Dark", 86, 106, 106),
+    Floss::new("926", "Gray Green Medium", 152, 174, 174),
+    Floss::new("927", "Gray Green Light", 189, 203, 203),
+    Floss::new("928", "Gray Green Very Light", 221, 227, 227),
+    Floss::new("930", "Antique Blue Dark", 69, 92, 113),
+    Floss::new("931", "Antique Blue Medium", 106, 133, 158),
+    Floss::new("932", "Antique Blue Light", 162, 181, 198),
+    Floss::new("934", "Black Avocado Green", 49, 57, 25),
+    Floss::new("935", "Avocado Green Dark", 66, 77, 33),
+    Floss::new("936", "Avocado Green Very Dark", 76, 88, 38),
+    Floss::new("937", "Avocado Green Medium", 98, 113, 51),
+    Floss::new("938", "Coffee Brown Ultra Dark", 54, 31, 14),
+    Floss::new("939", "Navy Blue Very Dark", 27, 40, 83),
+    Floss::new("943", "Aquamarine Medium", 61, 147, 132),
+    Floss::new("945", "Tawny", 251, 213, 187),
+    Floss::new("946", "Burnt Orange Medium", 235, 99, 7),
+    Floss::new("947", "Burnt Orange", 255, 123, 77),
+    Floss::new("948", "Peach Very Light", 254, 231, 218),
+    Floss::new("950", "Desert Sand Light", 238, 211, 196),
+    Floss::new("951", "Tawny Light", 255, 226, 207),
+    Floss::new("954", "Nile Green", 136, 186, 145),
+    Floss::new("955", "Nile Green Light", 162, 214, 173),
+    Floss::new("956", "Geranium", 255, 145, 145),
+    Floss::new("957", "Geranium Pale", 253, 181, 181),
+    Floss::new("958", "Sea Green Dark", 62, 182, 161),
+    Floss::new("959", "Sea Green Medium", 89, 199, 180),
+    Floss::new("961", "Dusty Rose Dark", 207, 115, 115),
+    Floss::new("962", "Dusty Rose Medium", 230, 138, 138),
+    Floss::new("963", "Dusty Rose Ultra Very Light", 255, 215, 215),
+    Floss::new("964", "Sea Green Light", 169, 226, 216),
+    Floss::new("966", "Jade Ultra Very Light", 185, 215, 192),
+    Floss::new("970", "Pumpkin Light", 247, 139, 19),
+    Floss::new("971", "Pumpkin", 246, 127, 0),
+    Floss::new("972", "Canary Deep", 255, 181, 21),
+    Floss::new("973", "Canary Bright", 255, 227, 0),
+    Floss::new("975", "Golden Brown Dark", 145, 79, 18),
+    Floss::new("976", "Golden Brown Medium", 194, 129, 66),
+    Floss::new("977", "Golden Brown Light", 220, 156, 86),
+    Floss::new("986", "Forest Green Very Dark", 64, 82, 48),
+    Floss::new("987", "Forest Green Dark", 88, 113, 65),
+    Floss::new("988", "Forest Green Medium", 115, 139, 91),
+    Floss::new("989", "Forest Green", 141, 166, 117),
+    Floss::new("991", "Aquamarine Dark", 71, 123, 110),
+    Floss::new("992", "Aquamarine Light", 111, 174, 159),
+    Floss::new("993", "Aquamarine Very Light", 144, 192, 180),
+    Floss::new("995", "Electric Blue Dark", 38, 150, 182),
+    Floss::new("996", "Electric Blue Medium", 48, 194, 236),
+    Floss::new("3011", "Khaki Green Dark", 137, 138, 88),
+    Floss::new("3012", "Khaki Green Medium", 166, 167, 93),
+    Floss::new("3013", "Khaki Green Light", 185, 185, 130),
+    Floss::new("3021", "Brown Gray Very Dark", 79, 75, 65),
+    Floss::new("3022", "Brown Gray Medium", 142, 144, 120),
+    Floss::new("3023", "Brown Gray Light", 177, 170, 151),
+    Floss::new("3024", "Brown Gray Very Light", 235, 234, 231),
+    Floss::new("3031", "Mocha Brown Very Dark", 75, 60, 42),
+    Floss::new("3032", "Mocha Brown Medium", 179, 159, 139),
+    Floss::new("3033", "Mocha Brown Very Light", 227, 216, 204),
+    Floss::new("3045", "Yellow Beige Dark", 188, 150, 106),
+    Floss::new("3046", "Yellow Beige Medium", 216, 188, 154),
+    Floss::new("3047", "Yellow Beige Light", 231, 214, 193),
+    Floss::new("3064", "Desert Sand", 196, 142, 112),
+    Floss::new("3072", "Beaver Gray Very Light", 230, 232, 232),
+    Floss::new("3078", "Golden Yellow Very Light", 253, 249, 205),
+    Floss::new("3325", "Baby Blue Light", 184, 210, 230),
+    Floss::new("3326", "Rose Light", 251, 173, 180),
+    Floss::new("3328", "Salmon Dark", 227, 109, 109),
+    Floss::new("3340", "Apricot Medium", 255, 131, 111),
+    Floss::new("3341", "Apricot", 252, 171, 152),
+    Floss::new("3345", "Hunter Green Dark", 27, 89, 21),
+    Floss::new("3346", "Hunter Green", 64, 106, 57),
+    Floss::new("3347", "Yellow Green Medium", 113, 147, 92),
+    Floss::new("3348", "Yellow Green Light", 204, 217, 177),
+    Floss::new("3350", "Dusty Rose Ultra Dark", 188, 67, 101),
+    Floss::new("3354", "Dusty Rose Light", 228, 166, 172),
+    Floss::new("3362", "Pine Green Dark", 94, 107, 71),
+    Floss::new("3363", "Pine Green Medium", 114, 130, 86),
+    Floss::new("3364", "Pine Green", 131, 151, 95),
+    Floss::new("3371", "Black Brown", 30, 17, 8),
+    Floss::new("3705", "Melon Dark", 255, 121, 146),
+    Floss::new("3706", "Melon Medium", 255, 173, 188),
+    Floss::new("3708", "Melon Light", 255, 203, 213),
+    Floss::new("3712", "Salmon Medium", 241, 135, 135),
+    Floss::new("3713", "Salmon Very Light", 255, 226, 226),
+    Floss::new("3716", "Dusty Rose Medium Very Light", 255, 189, 189),
+    Floss::new("3721", "Shell Pink Dark", 161, 75, 81),
+    Floss::new("3722", "Shell Pink Medium", 188, 108, 100),
+    Floss::new("3726", "Antique Mauve Dark", 155, 91, 102),
+    Floss::new("3727", "Antique Mauve Light", 219, 169, 178),
+    Floss::new("3731", "Dusty Rose Very Dark", 218, 103, 131),
+    Floss::new("3733", "Dusty Rose", 232, 135, 155),
+    Floss::new("3740", "Antique Violet Dark", 120, 88, 101),
+    Floss::new("3746", "Blue Violet Dark", 119, 107, 152),
+    Floss::new("3747", "Blue Violet Very Light", 211, 215, 237),
+    Floss::new("3750", "Antique Blue Very Dark", 56, 76, 94),
+    Floss::new("3752", "Antique Blue Very Light", 199, 209, 219),
+    Floss::new("3753", "Antique Blue Ultra Very Light", 219, 226, 233),
+    Floss::new("3755", "Baby Blue", 147, 180, 206),
+    Floss::new("3756", "Baby Blue Ultra Very Light", 238, 252, 252),
+    Floss::new("3760", "Wedgewood Medium", 62, 133, 162),
+    Floss::new("3761", "Sky Blue Light", 172, 216, 226),
+    Floss::new("3765", "Peacock Blue Very Dark", 52, 127, 140),
+    Floss::new("3766", "Peacock Blue Light", 153, 207, 217),
+    Floss::new("3768", "Gray Green Dark", 101, 127, 127),
+    Floss::new("3770", "Tawny Very Light", 255, 238, 227),
+    Floss::new("3772", "Desert Sand Very Dark", 160, 108, 80),
+    Floss::new("3774", "Desert Sand Very Light", 243, 225, 215),
+    Floss::new("3776", "Mahogany Light", 207, 121, 57),
+    Floss::new("3777", "Terra Cotta Very Dark", 134, 48, 34),
+    Floss::new("3778", "Terra Cotta Light", 217, 137, 120),
+    Floss::new("3779", "Rosewood Ultra Very Light", 248, 202, 200),
+    Floss::new("3781", "Mocha Brown Dark", 107, 87, 67),
+    Floss::new("3782", "Mocha Brown Light", 148, 123, 103),
+    Floss::new("3787", "Brown Gray Dark", 98, 93, 80),
+    Floss::new("3790", "Beige Gray Ultra Dark", 127, 106, 85),
+    Floss::new("3799", "Pewter Gray Very Dark", 66, 66, 66),
+    Floss::new("3801", "Melon Very Dark", 231, 73, 103),
+    Floss::new("3802", "Antique Mauve Very Dark", 113, 65, 73),
+    Floss::new("3803", "Mauve Dark", 171, 51, 87),
+    Floss::new("3804", "Cyclamen Pink Dark", 224, 40, 118),
+    Floss::new("3805", "Cyclamen Pink", 243, 71, 139),
+    Floss::new("3806", "Cyclamen Pink Light", 255, 140, 174),
+    Floss::new("3807", "Cornflower Blue", 96, 103, 140),
+    Floss::new("3808", "Turquoise Ultra Very Dark", 54, 105, 112),
+    Floss::new("3809", "Turquoise Very Dark", 63, 124, 133),
+    Floss::new("3810", "Turquoise Dark", 72, 142, 154),
+    Floss::new("3811", "Turquoise Very Light", 188, 227, 230),
+    Floss::new("3812", "Sea Green Very Dark", 47, 140, 132),
+    Floss::new("3813", "Blue Green Light", 178, 212, 189),
+    Floss::new("3814", "Aquamarine", 80, 139, 125),
+    Floss::new("3815", "Celadon Green Dark", 71, 119, 89),
+    Floss::new("3816", "Celadon Green", 101, 165, 125),
+    Floss::new("3817", "Celadon Green Light", 153, 195, 170),
+    Floss::new("3818", "Emerald Green Ultra Very Dark", 17, 90, 59),
+    Floss::new("3819", "Moss Green Light", 224, 232, 104),
+    Floss::new("3820", "Straw Dark", 223, 182, 95),
+    Floss::new("3821", "Straw", 243, 206, 117),
+    Floss::new("3822", "Straw Light", 246, 220, 152),
+    Floss::new("3823", "Yellow Ultra Pale", 255, 253, 227),
+    Floss::new("3824", "Apricot Light", 254, 205, 194),
+    Floss::new("3825", "Pumpkin Pale", 253, 189, 150),
+    Floss::new("3826", "Golden Brown", 173, 114, 57),
+    Floss::new("3827", "Golden Brown Pale", 247, 187, 119),
+    Floss::new("3828", "Hazelnut Brown", 183, 139, 97),
+    Floss::new("3829", "Old Gold Very Dark", 169, 130, 4),
+    Floss::new("3830", "Terra Cotta", 185, 85, 68),
+    Floss::new("3831", "Raspberry Dark", 179, 47, 72),
+    Floss::new("3832", "Raspberry Medium", 219, 85, 110),
+    Floss::new("3833", "Raspberry Light", 234, 134, 153),
+    Floss::new("3834", "Grape Dark", 114, 55, 93),
+    Floss::new("3835", "Grape Medium", 148, 96, 131),
+    Floss::new("3836", "Grape Light", 186, 145, 170),
+    Floss::new("3837", "Lavender Ultra Dark", 108, 58, 110),
+    Floss::new("3838", "Lavender Blue Dark", 92, 114, 148),
+    Floss::new("3839", "Lavender Blue Medium", 123, 142, 171),
+    Floss::new("3840", "Lavender Blue Light", 176, 192, 218),
+    Floss::new("3841", "Baby Blue Pale", 205, 223, 237),
+    Floss::new("3842", "Wedgewood Very Dark", 50, 102, 124),
+    Floss::new("3843", "Electric Blue", 20, 170, 208),
+    Floss::new("3844", "Bright Turquoise Dark", 18, 174, 186),
+    Floss::new("3845", "Bright Turquoise Medium", 4, 196, 202),
+    Floss::new("3846", "Bright Turquoise Light", 6, 227, 230),
+    Floss::new("3847", "Teal Green Dark", 52, 125, 117),
+    Floss::new("3848", "Teal Green Medium", 85, 147, 146),
+    Floss::new("3849", "Teal Green Light", 82, 179, 164),
+    Floss::new("3850", "Bright Green Dark", 55, 132, 119),
+    Floss::new("3851", "Bright Green Light", 73, 179, 161),
+    Floss::new("3852", "Straw Very Dark", 205, 157, 55),
+    Floss::new("3853", "Autumn Gold Dark", 242, 151, 70),
+    Floss::new("3854", "Autumn Gold Medium", 242, 175, 104),
+    Floss::new("3855", "Autumn Gold Light", 250, 211, 150),
+    Floss::new("3856", "Mahogany Ultra Very Light", 255, 211, 181),
+    Floss::new("3857", "Rosewood Dark", 104, 37, 26),
+    Floss::new("3858", "Rosewood Medium", 150, 74, 63),
+    Floss::new("3859", "Rosewood Light", 186, 139, 124),
+    Floss::new("3860", "Cocoa", 125, 93, 87),
+    Floss::new("3861", "Cocoa Light", 166, 136, 129),
+    Floss::new("3862", "Mocha Beige Dark", 138, 110, 78),
+    Floss::new("3863", "Mocha Beige Medium", 164, 131, 92),
+    Floss::new("3864", "Mocha Beige Light", 203, 182, 156),
+    Floss::new("3865", "Winter White", 255, 255, 253),
+    Floss::new("3866", "Mocha Brown Ultra Very Light", 250, 246, 240),
+];
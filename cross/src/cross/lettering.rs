@@ -0,0 +1,354 @@
+//! Renders text to the stitch grid, with built-in cross-stitch alphabets or any TrueType font
+use ab_glyph::{point, Font, FontVec, PxScale, ScaleFont};
+use serde::{Deserialize, Serialize};
+
+use std::io;
+use std::path::Path;
+
+// Stitches between letters, and rows between lines
+const LETTER_SPACING: usize = 1;
+const LINE_SPACING: usize = 1;
+
+// TrueType glyphs are stitched wherever they cover at least this much of a stitch.
+const MIN_COVERAGE: f32 = 0.5;
+
+/// Stitches to fill, as text (or another motif) rendered to the stitch grid.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Bitmap {
+    pub width: usize,
+    pub height: usize,
+
+    // Row-major, true where there's a stitch
+    pub cells: Vec<bool>,
+}
+
+impl Bitmap {
+    pub fn new(width: usize, height: usize) -> Self {
+        Bitmap { width, height, cells: vec![false; width * height] }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.cells[x + y * self.width]
+    }
+
+    fn set(&mut self, x: usize, y: usize) {
+        self.cells[x + y * self.width] = true;
+    }
+
+    /// Copies another bitmap's stitches in, clipped to this one.
+    fn draw(&mut self, other: &Bitmap, left: usize, top: usize) {
+        for y in 0..other.height.min(self.height.saturating_sub(top)) {
+            for x in 0..other.width.min(self.width.saturating_sub(left)) {
+                if other.get(x, y) {
+                    self.set(left + x, top + y);
+                }
+            }
+        }
+    }
+
+    /// Each stitch becomes a square block of stitches.
+    fn scaled(&self, factor: usize) -> Bitmap {
+        let mut scaled = Bitmap::new(self.width * factor, self.height * factor);
+        for y in 0..scaled.height {
+            for x in 0..scaled.width {
+                if self.get(x / factor, y / factor) {
+                    scaled.set(x, y);
+                }
+            }
+        }
+
+        scaled
+    }
+
+    /// Stacks lines of text, centering each one.
+    fn stack(lines: &[Bitmap]) -> Bitmap {
+        let width = lines.iter().map(|line| line.width).max().unwrap_or(0);
+        let height = lines.iter().map(|line| line.height).sum::<usize>() + LINE_SPACING * lines.len().saturating_sub(1);
+        let mut stacked = Bitmap::new(width, height);
+        let mut top = 0;
+        for line in lines {
+            stacked.draw(line, (width - line.width) / 2, top);
+            top += line.height + LINE_SPACING;
+        }
+
+        stacked
+    }
+}
+
+/// Built-in alphabets, designed stitch by stitch so they stay legible at small sizes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Alphabet {
+    Small,
+    Medium,
+    // The medium alphabet with every stitch doubled, for bold headings
+    Large,
+}
+
+impl Alphabet {
+    pub const ALL: [Alphabet; 3] = [Alphabet::Small, Alphabet::Medium, Alphabet::Large];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Alphabet::Small => "Small (5 rows)",
+            Alphabet::Medium => "Medium (7 rows)",
+            Alphabet::Large => "Large (14 rows)",
+        }
+    }
+
+    fn glyphs(self) -> &'static [(char, &'static str)] {
+        match self {
+            Alphabet::Small => SMALL,
+            Alphabet::Medium | Alphabet::Large => MEDIUM,
+        }
+    }
+
+    /// The alphabets are uppercase only. Other missing characters are shown as '?'.
+    fn glyph(self, c: char) -> Bitmap {
+        let glyphs = self.glyphs();
+        let c = c.to_ascii_uppercase();
+        let (_, rows) = glyphs.iter().find(|(glyph, _)| *glyph == c)
+            .or_else(|| glyphs.iter().find(|(glyph, _)| *glyph == '?'))
+            .expect("every alphabet has '?'");
+
+        let rows: Vec<&str> = rows.split(' ').collect();
+        let mut bitmap = Bitmap::new(rows[0].len(), rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, cell) in row.chars().enumerate() {
+                if cell == '#' {
+                    bitmap.set(x, y);
+                }
+            }
+        }
+
+        bitmap
+    }
+
+    fn render_line(self, line: &str) -> Bitmap {
+        let glyphs: Vec<Bitmap> = line.chars().map(|c| self.glyph(c)).collect();
+        let width = glyphs.iter().map(|glyph| glyph.width).sum::<usize>() + LETTER_SPACING * glyphs.len().saturating_sub(1);
+        let mut bitmap = Bitmap::new(width, self.glyph(' ').height);
+        let mut left = 0;
+        for glyph in &glyphs {
+            bitmap.draw(glyph, left, 0);
+            left += glyph.width + LETTER_SPACING;
+        }
+
+        bitmap
+    }
+
+    pub fn render(self, text: &str) -> Bitmap {
+        let lines: Vec<Bitmap> = text.lines().map(|line| self.render_line(line)).collect();
+        let bitmap = Bitmap::stack(&lines);
+        match self {
+            Alphabet::Large => bitmap.scaled(2),
+            _ => bitmap,
+        }
+    }
+}
+
+/// A font file loaded from disk.
+pub struct TrueTypeFont {
+    pub name: String,
+    font: FontVec,
+}
+
+impl TrueTypeFont {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let font = FontVec::try_from_vec(std::fs::read(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let name = path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().to_string());
+        Ok(TrueTypeFont { name, font })
+    }
+
+    /// Rasterizes one line `height` stitches tall, from the top of the tallest letters to the bottom of the lowest.
+    /// Letters are narrowed on grids with wide cells (`aspect` above 1) so they keep their shape once stitched.
+    fn render_line(&self, line: &str, height: usize, aspect: f32) -> Bitmap {
+        let scale = PxScale { x: height as f32 / aspect, y: height as f32 };
+        let scaled = self.font.as_scaled(scale);
+
+        let mut glyphs = vec![];
+        let mut caret = 0.0;
+        let mut previous = None;
+        for c in line.chars() {
+            let id = scaled.glyph_id(c);
+            if let Some(previous) = previous {
+                caret += scaled.kern(previous, id);
+            }
+            glyphs.push(id.with_scale_and_position(scale, point(caret, scaled.ascent())));
+            caret += scaled.h_advance(id);
+            previous = Some(id);
+        }
+
+        let mut bitmap = Bitmap::new(caret.ceil() as usize, height);
+        for glyph in glyphs {
+            let Some(outline) = self.font.outline_glyph(glyph) else {
+                continue;
+            };
+
+            let bounds = outline.px_bounds();
+            outline.draw(|x, y, coverage| {
+                let (x, y) = (bounds.min.x as i32 + x as i32, bounds.min.y as i32 + y as i32);
+                if coverage >= MIN_COVERAGE && x >= 0 && y >= 0 && (x as usize) < bitmap.width && (y as usize) < bitmap.height {
+                    bitmap.set(x as usize, y as usize);
+                }
+            });
+        }
+
+        bitmap
+    }
+
+    pub fn render(&self, text: &str, height: usize, aspect: f32) -> Bitmap {
+        let lines: Vec<Bitmap> = text.lines().map(|line| self.render_line(line, height, aspect)).collect();
+        Bitmap::stack(&lines)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Typeface {
+    Builtin(Alphabet),
+    TrueType,
+}
+
+/// The text being set up for placing on the chart.
+pub struct Lettering {
+    pub text: String,
+    pub typeface: Typeface,
+
+    // Only used with the TrueType typeface
+    pub font: Option<TrueTypeFont>,
+    pub font_height: usize,
+
+    // Catalog code of the floss (or yarn or bead) to stitch the text in
+    pub floss_code: String,
+}
+
+impl Default for Lettering {
+    fn default() -> Self {
+        Lettering {
+            text: String::new(),
+            typeface: Typeface::Builtin(Alphabet::Medium),
+            font: None,
+            font_height: 12,
+            floss_code: "310".to_string(),
+        }
+    }
+}
+
+impl Lettering {
+    /// None if there's nothing to render yet.
+    pub fn render(&self, aspect: f32) -> Option<Bitmap> {
+        if self.text.trim().is_empty() {
+            return None;
+        }
+
+        match self.typeface {
+            Typeface::Builtin(alphabet) => Some(alphabet.render(&self.text)),
+            Typeface::TrueType => self.font.as_ref().map(|font| font.render(&self.text, self.font_height, aspect)),
+        }
+    }
+}
+
+// Each glyph is its rows of stitches ('#') from top to bottom, separated by spaces.
+const SMALL: &[(char, &str)] = &[
+    ('A', ".#. #.# ### #.# #.#"),
+    ('B', "##. #.# ##. #.# ##."),
+    ('C', ".## #.. #.. #.. .##"),
+    ('D', "##. #.# #.# #.# ##."),
+    ('E', "### #.. ##. #.. ###"),
+    ('F', "### #.. ##. #.. #.."),
+    ('G', ".## #.. #.# #.# .##"),
+    ('H', "#.# #.# ### #.# #.#"),
+    ('I', "### .#. .#. .#. ###"),
+    ('J', "..# ..# ..# #.# .#."),
+    ('K', "#.# #.# ##. #.# #.#"),
+    ('L', "#.. #.. #.. #.. ###"),
+    ('M', "#.# ### ### #.# #.#"),
+    ('N', "##. #.# #.# #.# #.#"),
+    ('O', ".#. #.# #.# #.# .#."),
+    ('P', "##. #.# ##. #.. #.."),
+    ('Q', ".#. #.# #.# ##. .##"),
+    ('R', "##. #.# ##. #.# #.#"),
+    ('S', ".## #.. .#. ..# ##."),
+    ('T', "### .#. .#. .#. .#."),
+    ('U', "#.# #.# #.# #.# ###"),
+    ('V', "#.# #.# #.# #.# .#."),
+    ('W', "#.# #.# ### ### #.#"),
+    ('X', "#.# #.# .#. #.# #.#"),
+    ('Y', "#.# #.# .#. .#. .#."),
+    ('Z', "### ..# .#. #.. ###"),
+    ('0', "### #.# #.# #.# ###"),
+    ('1', ".#. ##. .#. .#. ###"),
+    ('2', "##. ..# .#. #.. ###"),
+    ('3', "##. ..# .#. ..# ##."),
+    ('4', "#.# #.# ### ..# ..#"),
+    ('5', "### #.. ##. ..# ##."),
+    ('6', ".## #.. ### #.# ###"),
+    ('7', "### ..# .#. .#. .#."),
+    ('8', "### #.# ### #.# ###"),
+    ('9', "### #.# ### ..# ##."),
+    (' ', ".. .. .. .. .."),
+    ('.', ". . . . #"),
+    (',', ".. .. .. .# #."),
+    ('!', "# # # . #"),
+    ('?', "##. ..# .#. ... .#."),
+    ('-', "... ... ### ... ..."),
+    (':', ". # . # ."),
+    ('\'', "# # . . ."),
+    ('&', ".#. #.# .#. #.# .##"),
+    ('/', "..# ..# .#. #.. #.."),
+    ('+', "... .#. ### .#. ..."),
+    ('(', ".# #. #. #. .#"),
+    (')', "#. .# .# .# #."),
+];
+
+const MEDIUM: &[(char, &str)] = &[
+    ('A', ".###. #...# #...# ##### #...# #...# #...#"),
+    ('B', "####. #...# #...# ####. #...# #...# ####."),
+    ('C', ".###. #...# #.... #.... #.... #...# .###."),
+    ('D', "###.. #..#. #...# #...# #...# #..#. ###.."),
+    ('E', "##### #.... #.... ####. #.... #.... #####"),
+    ('F', "##### #.... #.... ####. #.... #.... #...."),
+    ('G', ".###. #...# #.... #.### #...# #...# .####"),
+    ('H', "#...# #...# #...# ##### #...# #...# #...#"),
+    ('I', ".###. ..#.. ..#.. ..#.. ..#.. ..#.. .###."),
+    ('J', "..### ...#. ...#. ...#. ...#. #..#. .##.."),
+    ('K', "#...# #..#. #.#.. ##... #.#.. #..#. #...#"),
+    ('L', "#.... #.... #.... #.... #.... #.... #####"),
+    ('M', "#...# ##.## #.#.# #.#.# #...# #...# #...#"),
+    ('N', "#...# #...# ##..# #.#.# #..## #...# #...#"),
+    ('O', ".###. #...# #...# #...# #...# #...# .###."),
+    ('P', "####. #...# #...# ####. #.... #.... #...."),
+    ('Q', ".###. #...# #...# #...# #.#.# #..#. .##.#"),
+    ('R', "####. #...# #...# ####. #.#.. #..#. #...#"),
+    ('S', ".#### #.... #.... .###. ....# ....# ####."),
+    ('T', "##### ..#.. ..#.. ..#.. ..#.. ..#.. ..#.."),
+    ('U', "#...# #...# #...# #...# #...# #...# .###."),
+    ('V', "#...# #...# #...# #...# #...# .#.#. ..#.."),
+    ('W', "#...# #...# #...# #.#.# #.#.# #.#.# .#.#."),
+    ('X', "#...# #...# .#.#. ..#.. .#.#. #...# #...#"),
+    ('Y', "#...# #...# .#.#. ..#.. ..#.. ..#.. ..#.."),
+    ('Z', "##### ....# ...#. ..#.. .#... #.... #####"),
+    ('0', ".###. #...# #..## #.#.# ##..# #...# .###."),
+    ('1', "..#.. .##.. ..#.. ..#.. ..#.. ..#.. .###."),
+    ('2', ".###. #...# ....# ...#. ..#.. .#... #####"),
+    ('3', "##### ...#. ..#.. ...#. ....# #...# .###."),
+    ('4', "...#. ..##. .#.#. #..#. ##### ...#. ...#."),
+    ('5', "##### #.... ####. ....# ....# #...# .###."),
+    ('6', "..##. .#... #.... ####. #...# #...# .###."),
+    ('7', "##### ....# ...#. ..#.. .#... .#... .#..."),
+    ('8', ".###. #...# #...# .###. #...# #...# .###."),
+    ('9', ".###. #...# #...# .#### ....# ...#. .##.."),
+    (' ', "... ... ... ... ... ... ..."),
+    ('.', ".. .. .. .. .. ## ##"),
+    (',', ".. .. .. .. ## .# #."),
+    ('!', "# # # # # . #"),
+    ('?', ".###. #...# ....# ...#. ..#.. ..... ..#.."),
+    ('-', "..... ..... ..... ##### ..... ..... ....."),
+    (':', ".. ## ## .. ## ## .."),
+    ('\'', "## .# #. .. .. .. .."),
+    ('&', ".##.. #..#. #.#.. .#... #.#.# #..#. .##.#"),
+    ('/', "..... ....# ...#. ..#.. .#... #.... ....."),
+    ('+', "..... ..#.. ..#.. ##### ..#.. ..#.. ....."),
+    ('(', "..# .#. #.. #.. #.. .#. ..#"),
+    (')', "#.. .#. ..# ..# ..# .#. #.."),
+];
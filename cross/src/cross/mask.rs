@@ -0,0 +1,71 @@
+//! Marks the parts of an image that matter most, so they keep their detail in the chart
+use crate::egui::{Color32, ColorImage};
+
+use serde::{Deserialize, Serialize};
+
+// The mask is painted over the thumbnail, so it doesn't need to be finer than this, whatever the image size.
+const MASK_WIDTH: usize = 100;
+
+/// Coarse grid over the whole image, independent of the chart size.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Mask {
+    width: usize,
+    height: usize,
+
+    // Row-major, true where the image is important
+    cells: Vec<bool>,
+}
+
+impl Mask {
+    /// An empty mask with the image's aspect ratio.
+    pub fn for_image(size: [usize; 2]) -> Self {
+        let width = MASK_WIDTH;
+        let height = ((MASK_WIDTH * size[1]) as f32 / size[0].max(1) as f32).round().max(1.0) as usize;
+        Mask { width, height, cells: vec![false; width * height] }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.cells.contains(&true)
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.fill(false);
+    }
+
+    /// Marks (or unmarks) a circle around a point, with the position and radius as fractions of the image width.
+    pub fn paint(&mut self, u: f32, v: f32, radius: f32, important: bool) {
+        let scale = self.width as f32;
+        let (center_x, center_y, radius) = (u * scale, v * self.height as f32, radius * scale);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (dx, dy) = (x as f32 + 0.5 - center_x, y as f32 + 0.5 - center_y);
+                if dx * dx + dy * dy <= radius * radius {
+                    self.cells[x + y * self.width] = important;
+                }
+            }
+        }
+    }
+
+    /// Whether each stitch of a chart of the given size falls in the masked area, row-major.
+    pub fn sample(&self, width: usize, height: usize) -> Vec<bool> {
+        // The default mask has no cells at all, so nothing is masked.
+        if self.cells.is_empty() {
+            return vec![false; width * height];
+        }
+
+        (0..height).flat_map(|y| (0..width).map(move |x| (x, y))).map(|(x, y)| {
+            let mask_x = ((x as f32 + 0.5) / width as f32 * self.width as f32) as usize;
+            let mask_y = ((y as f32 + 0.5) / height as f32 * self.height as f32) as usize;
+            self.cells[mask_x.min(self.width - 1) + mask_y.min(self.height - 1) * self.width]
+        }).collect()
+    }
+
+    /// Tints the masked area, for drawing over the image.
+    pub fn overlay(&self) -> ColorImage {
+        let tint = Color32::from_rgba_unmultiplied(255, 0, 255, 110);
+        ColorImage {
+            size: [self.width, self.height],
+            pixels: self.cells.iter().map(|important| if *important { tint } else { Color32::TRANSPARENT }).collect(),
+        }
+    }
+}
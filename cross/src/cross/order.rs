@@ -0,0 +1,290 @@
+//! Suggests how to work each color, in what order, and where the thread would travel too far across the back
+use crate::egui::{Color32, ColorImage};
+
+use super::renderer::{ChartData, MAJOR_GRID_SPACING};
+
+// Colors whose areas average at least this many stitches are filled one area at a time.
+const LARGE_REGION: f32 = 12.0;
+
+// Colors sharing their 10x10 blocks with at least this many colors (themselves included) are parked.
+const PARKING_COLORS: f32 = 5.0;
+
+/// How to work one color
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    // Fill each area of the color before moving to the next, ending the thread between far-apart areas
+    RegionByRegion,
+    // Work the color's stitches across each band of rows, carrying the thread between nearby stitches
+    RowByRow,
+    // Work a band of rows at a time, parking the thread at its next stitch below instead of carrying it
+    Parking,
+}
+
+impl Method {
+    pub fn name(self) -> &'static str {
+        match self {
+            Method::RegionByRegion => "Region by region",
+            Method::RowByRow => "Row by row",
+            Method::Parking => "Parking",
+        }
+    }
+}
+
+/// A stretch of one color worked in one go: a whole area, or the color's stitches in a band of rows.
+pub struct Step {
+    pub palette_index: usize,
+
+    // Row-major, so the first stitch is where the step starts
+    pub stitches: Vec<usize>,
+
+    // Stitches where the thread is ended and started again, as the color's previous stitch is too far away to carry it
+    pub restarts: Vec<usize>,
+}
+
+/// The plan for one color
+pub struct ColorPlan {
+    pub palette_index: usize,
+    pub method: Method,
+    pub stitches: usize,
+
+    // Separate (4-connected) areas of the color
+    pub regions: usize,
+
+    // Thread carried across the back, in stitches, and how many carries were too long
+    pub travel: f32,
+    pub long_travels: usize,
+    pub thread_starts: usize,
+}
+
+/// The statistics behind the difficulty score
+pub struct Difficulty {
+    pub colors: usize,
+    pub stitches: usize,
+
+    // Stitches with no same-colored stitch beside them
+    pub confetti: usize,
+
+    // Average number of colors in each 10x10 block with stitches in it
+    pub colors_per_block: f32,
+
+    pub thread_starts: usize,
+    pub long_travels: usize,
+    pub travel: f32,
+
+    // From 1 (one solid color) to 10
+    pub score: f32,
+}
+
+pub struct StitchOrder {
+    // In the order they're worked
+    pub colors: Vec<ColorPlan>,
+    pub steps: Vec<Step>,
+    pub difficulty: Difficulty,
+}
+
+/// Inclusive (first column, first row, last column, last row)
+type Bounds = (usize, usize, usize, usize);
+
+fn bounds(stitches: &[usize], width: usize) -> Bounds {
+    let mut bounds = (usize::MAX, usize::MAX, 0, 0);
+    for stitch in stitches {
+        let (x, y) = (stitch % width, stitch / width);
+        bounds = (bounds.0.min(x), bounds.1.min(y), bounds.2.max(x), bounds.3.max(y));
+    }
+
+    bounds
+}
+
+/// Straight-line distance between the nearest edges of two areas' bounds. Zero if they overlap.
+fn gap(a: Bounds, b: Bounds) -> f32 {
+    let dx = a.0.saturating_sub(b.2).max(b.0.saturating_sub(a.2)).saturating_sub(1);
+    let dy = a.1.saturating_sub(b.3).max(b.1.saturating_sub(a.3)).saturating_sub(1);
+    ((dx * dx + dy * dy) as f32).sqrt()
+}
+
+/// Gaps skipped along each row, as carried when working a band row by row, with the stitch after each gap.
+fn row_gaps(stitches: &[usize], width: usize) -> Vec<(usize, f32)> {
+    stitches.windows(2)
+        .filter(|pair| pair[0] / width == pair[1] / width && pair[1] > pair[0] + 1)
+        .map(|pair| (pair[1], (pair[1] - pair[0] - 1) as f32))
+        .collect()
+}
+
+/// Orders areas by always moving to the nearest one not yet worked, starting from the top.
+fn nearest_first(mut areas: Vec<Vec<usize>>, width: usize) -> Vec<Vec<usize>> {
+    areas.sort_by_key(|area| area[0]);
+    let mut remaining: Vec<(Vec<usize>, Bounds)> = areas.into_iter().map(|area| {
+        let area_bounds = bounds(&area, width);
+        (area, area_bounds)
+    }).collect();
+
+    let mut ordered = vec![];
+    let mut current = remaining.remove(0);
+    while !remaining.is_empty() {
+        let next = (0..remaining.len())
+            .min_by(|a, b| gap(current.1, remaining[*a].1).total_cmp(&gap(current.1, remaining[*b].1)))
+            .expect("remaining isn't empty");
+        ordered.push(std::mem::replace(&mut current, remaining.remove(next)).0);
+    }
+    ordered.push(current.0);
+
+    ordered
+}
+
+/// Plans every color, most-used first. Carries longer than `max_travel` stitches end the thread instead.
+pub fn plan(chart_data: &ChartData, max_travel: f32) -> StitchOrder {
+    let (width, height) = (chart_data.width, chart_data.height);
+    let colors = chart_data.palette.len();
+
+    let mut regions: Vec<Vec<Vec<usize>>> = vec![vec![]; colors];
+    let mut visited = vec![false; chart_data.stitches.len()];
+    for start in 0..chart_data.stitches.len() {
+        if !visited[start] {
+            regions[chart_data.stitches[start]].push(chart_data.unvisited_region_at(start, &mut visited));
+        }
+    }
+
+    let confetti = regions.iter().flatten().filter(|region| region.len() == 1).count();
+
+    // Colors in each 10x10 block, as a bit of how busy the area around each stitch is
+    let blocks_across = width.div_ceil(MAJOR_GRID_SPACING);
+    let block_of = |stitch: usize| (stitch % width) / MAJOR_GRID_SPACING + (stitch / width) / MAJOR_GRID_SPACING * blocks_across;
+    let mut block_palettes: Vec<Vec<usize>> = vec![vec![]; blocks_across * height.div_ceil(MAJOR_GRID_SPACING)];
+    for (stitch, palette_index) in chart_data.stitches.iter().enumerate() {
+        let palette = &mut block_palettes[block_of(stitch)];
+        if !palette.contains(palette_index) {
+            palette.push(*palette_index);
+        }
+    }
+
+    let counts = chart_data.color_counts();
+    let mut color_order: Vec<usize> = (0..colors).filter(|i| counts[*i] > 0).collect();
+    color_order.sort_by_key(|i| std::cmp::Reverse(counts[*i]));
+
+    let mut color_plans = vec![];
+    let mut steps = vec![];
+    for palette_index in color_order {
+        let stitches: Vec<usize> = (0..chart_data.stitches.len()).filter(|stitch| chart_data.stitches[*stitch] == palette_index).collect();
+        let region_count = regions[palette_index].len();
+        let average_region = stitches.len() as f32 / region_count as f32;
+        let busyness = stitches.iter().map(|stitch| block_palettes[block_of(*stitch)].len()).sum::<usize>() as f32 / stitches.len() as f32;
+        let method = if average_region >= LARGE_REGION {
+            Method::RegionByRegion
+        } else if busyness >= PARKING_COLORS {
+            Method::Parking
+        } else {
+            Method::RowByRow
+        };
+
+        // Areas are worked nearest first. Bands are worked top to bottom, as the chart is read.
+        let units = match method {
+            Method::RegionByRegion => nearest_first(std::mem::take(&mut regions[palette_index]), width),
+            Method::RowByRow | Method::Parking => {
+                let mut bands: Vec<Vec<usize>> = vec![];
+                for stitch in &stitches {
+                    match bands.last_mut() {
+                        Some(band) if band[0] / width / MAJOR_GRID_SPACING == stitch / width / MAJOR_GRID_SPACING => band.push(*stitch),
+                        _ => bands.push(vec![*stitch]),
+                    }
+                }
+                bands
+            },
+        };
+
+        // Parked threads wait at their next stitch, so they never travel far or need restarting.
+        let mut color_plan = ColorPlan { palette_index, method, stitches: stitches.len(), regions: region_count, travel: 0.0, long_travels: 0, thread_starts: 1 };
+        let carry = |distance: f32, color_plan: &mut ColorPlan| {
+            if method == Method::Parking {
+                return false;
+            }
+
+            let restart = distance > max_travel;
+            if restart {
+                color_plan.long_travels += 1;
+                color_plan.thread_starts += 1;
+            } else {
+                color_plan.travel += distance;
+            }
+            restart
+        };
+
+        let mut previous_bounds = None;
+        for unit in units {
+            let unit_bounds = bounds(&unit, width);
+            let travel = previous_bounds.map_or(0.0, |previous| gap(previous, unit_bounds));
+            let mut restarts = vec![];
+            if carry(travel, &mut color_plan) {
+                restarts.push(unit[0]);
+            }
+            if method == Method::RowByRow {
+                for (stitch, row_gap) in row_gaps(&unit, width) {
+                    if carry(row_gap, &mut color_plan) {
+                        restarts.push(stitch);
+                    }
+                }
+            }
+
+            previous_bounds = Some(unit_bounds);
+            steps.push(Step { palette_index, stitches: unit, restarts });
+        }
+        color_plans.push(color_plan);
+    }
+
+    let difficulty = difficulty(chart_data, &color_plans, &block_palettes, confetti);
+    StitchOrder { colors: color_plans, steps, difficulty }
+}
+
+fn difficulty(chart_data: &ChartData, color_plans: &[ColorPlan], block_palettes: &[Vec<usize>], confetti: usize) -> Difficulty {
+    let stitches = chart_data.stitches.len();
+    let used_blocks: Vec<usize> = block_palettes.iter().map(Vec::len).filter(|colors| *colors > 0).collect();
+    let colors_per_block = used_blocks.iter().sum::<usize>() as f32 / used_blocks.len().max(1) as f32;
+    let thread_starts = color_plans.iter().map(|plan| plan.thread_starts).sum::<usize>();
+
+    // Each part is scaled to 0-1, where 1 is about as hard as patterns get:
+    // ten colors in every block, a tenth of stitches on their own, or five thread starts per hundred stitches.
+    let busyness = ((colors_per_block - 1.0) / 9.0).clamp(0.0, 1.0);
+    let confetti_share = (confetti as f32 / stitches.max(1) as f32 * 10.0).min(1.0);
+    let starts = (thread_starts as f32 / stitches.max(1) as f32 * 100.0 / 5.0).min(1.0);
+    let palette_size = (color_plans.len() as f32 / 50.0).min(1.0);
+
+    Difficulty {
+        colors: color_plans.len(),
+        stitches,
+        confetti,
+        colors_per_block,
+        thread_starts,
+        long_travels: color_plans.iter().map(|plan| plan.long_travels).sum(),
+        travel: color_plans.iter().map(|plan| plan.travel).sum(),
+        score: 1.0 + 9.0 * (0.35 * busyness + 0.3 * confetti_share + 0.25 * starts + 0.1 * palette_size),
+    }
+}
+
+impl StitchOrder {
+    /// Step numbers, from 1, at the first stitch of each step. Limited to one color if given.
+    pub fn labels(&self, palette_index: Option<usize>) -> Vec<(usize, String)> {
+        self.steps.iter().enumerate()
+            .filter(|(_, step)| palette_index.unwrap_or(step.palette_index) == step.palette_index)
+            .map(|(i, step)| (step.stitches[0], (i + 1).to_string()))
+            .collect()
+    }
+
+    /// Marks where threads are ended and restarted in red, and dims other colors if one is picked.
+    pub fn overlay(&self, chart_data: &ChartData, palette_index: Option<usize>) -> ColorImage {
+        let mut image = ColorImage::new([chart_data.width, chart_data.height], Color32::TRANSPARENT);
+        if let Some(palette_index) = palette_index {
+            for (pixel, stitch) in image.pixels.iter_mut().zip(&chart_data.stitches) {
+                if *stitch != palette_index {
+                    *pixel = Color32::from_black_alpha(170);
+                }
+            }
+        }
+
+        for step in self.steps.iter().filter(|step| palette_index.unwrap_or(step.palette_index) == step.palette_index) {
+            for stitch in &step.restarts {
+                image.pixels[*stitch] = Color32::from_rgba_unmultiplied(255, 0, 0, 160);
+            }
+        }
+
+        image
+    }
+}
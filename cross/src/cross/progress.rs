@@ -0,0 +1,102 @@
+//! Tracks which stitches have been completed, and how quickly
+use crate::egui::{Color32, ColorImage};
+
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::renderer::ChartData;
+
+const SECONDS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Progress {
+    // Size of the chart the progress was made on. Zero in projects saved before it was kept.
+    #[serde(default)]
+    width: usize,
+    #[serde(default)]
+    height: usize,
+
+    // One entry per stitch, row-major
+    done: Vec<bool>,
+
+    // Unix time (seconds) when the first stitch was marked done, to measure the actual pace.
+    started: Option<u64>,
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs())
+}
+
+impl Progress {
+    /// Starts over if the chart dimensions have changed, even if the stitch count hasn't, as with 40×30 to 30×40.
+    /// Progress survives recoloring, as the same stitches have still been worked.
+    pub fn fit(&mut self, chart_data: &ChartData) {
+        self.fit_size(chart_data.width, chart_data.height);
+    }
+
+    /// Like [`Self::fit`], for a project's saved chart. Progress saved without its size is taken to be for the project's chart.
+    pub fn fit_saved(&mut self, width: usize, height: usize) {
+        if self.width == 0 && self.height == 0 {
+            (self.width, self.height) = (width, height);
+        }
+        self.fit_size(width, height);
+    }
+
+    fn fit_size(&mut self, width: usize, height: usize) {
+        if (self.width, self.height) != (width, height) || self.done.len() != width * height {
+            *self = Progress {
+                width,
+                height,
+                done: vec![false; width * height],
+                started: None,
+            };
+        }
+    }
+
+    /// Toggles a stitch, or the whole same-colored block around it.
+    pub fn toggle(&mut self, chart_data: &ChartData, stitch: usize, whole_block: bool) {
+        let stitches = if whole_block { chart_data.region_at(stitch) } else { vec![stitch] };
+        let done = !self.done[stitch];
+        for stitch in stitches {
+            self.done[stitch] = done;
+        }
+
+        if done && self.started.is_none() {
+            self.started = Some(now());
+        }
+    }
+
+    pub fn done_count(&self) -> usize {
+        self.done.iter().filter(|done| **done).count()
+    }
+
+    /// (done, total) stitches for each palette color
+    pub fn color_counts(&self, chart_data: &ChartData) -> Vec<(usize, usize)> {
+        let mut counts = vec![(0, 0); chart_data.palette.len()];
+        for (palette_index, done) in chart_data.stitches.iter().zip(&self.done) {
+            let (color_done, color_total) = &mut counts[*palette_index];
+            *color_total += 1;
+            if *done {
+                *color_done += 1;
+            }
+        }
+
+        counts
+    }
+
+    /// Average stitches per day since the first stitch was marked, counting the first day as a whole day.
+    pub fn pace(&self) -> Option<f64> {
+        let started = self.started?;
+        let days = ((now().saturating_sub(started)) as f64 / SECONDS_PER_DAY).max(1.0);
+        Some(self.done_count() as f64 / days)
+    }
+
+    /// Washes out completed stitches, for drawing over the chart.
+    pub fn overlay(&self, chart_data: &ChartData) -> ColorImage {
+        let dimmed = Color32::from_rgba_unmultiplied(255, 255, 255, 190);
+        ColorImage {
+            size: [chart_data.width, chart_data.height],
+            pixels: self.done.iter().map(|done| if *done { dimmed } else { Color32::TRANSPARENT }).collect(),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Saves and opens a pattern, along with the settings that made it and stitching progress
+use crate::egui::Color32;
+
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::colorway::Colorway;
+use super::border;
+use super::config::Config;
+use super::decorations::Decorations;
+use super::mask::Mask;
+use super::progress::Progress;
+use super::renderer::{ChartData, PaletteColor};
+
+// Bump when the file layout changes in a way older versions can't read.
+const PROJECT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct SavedColor {
+    rgb: [u8; 3],
+    floss: String,
+
+    // Second floss of a blend
+    #[serde(default)]
+    blend: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Project {
+    version: u32,
+
+    // Source image, reloaded (but not reanalyzed) when the project is opened
+    pub image_path: Option<PathBuf>,
+    pub config: Config,
+
+    width: usize,
+    height: usize,
+    palette: Vec<SavedColor>,
+    stitches: Vec<usize>,
+
+    pub progress: Progress,
+
+    // Importance mask painted over the source image
+    #[serde(default)]
+    pub mask: Mask,
+
+    // Text and the like added over the chart. The saved stitches are the chart as analyzed, without them.
+    #[serde(default)]
+    pub decorations: Decorations,
+
+    // Alternate colorings of the chart
+    #[serde(default)]
+    pub colorways: Vec<Colorway>,
+}
+
+impl Project {
+    pub fn new(image_path: Option<PathBuf>, config: &Config, chart_data: &ChartData, progress: &Progress, mask: &Mask, decorations: &Decorations, colorways: &[Colorway]) -> Self {
+        Project {
+            version: PROJECT_VERSION,
+            image_path,
+            config: config.clone(),
+            width: chart_data.width,
+            height: chart_data.height,
+            palette: chart_data.palette.iter().map(|color| SavedColor {
+                rgb: [color.color.r(), color.color.g(), color.color.b()],
+                floss: color.floss.code.to_string(),
+                blend: color.blend.map(|blend| blend.code.to_string()),
+            }).collect(),
+            stitches: chart_data.stitches.clone(),
+            progress: progress.clone(),
+            mask: mask.clone(),
+            decorations: decorations.clone(),
+            colorways: colorways.to_vec(),
+        }
+    }
+
+    pub fn chart_data(&self) -> ChartData {
+        ChartData {
+            width: self.width,
+            height: self.height,
+            geometry: self.config.craft.geometry(&self.config),
+            catalog: self.config.craft.catalog(),
+            palette: self.palette.iter().map(|saved| {
+                let color = Color32::from_rgb(saved.rgb[0], saved.rgb[1], saved.rgb[2]);
+
+                // Fall back to the closest floss if the catalog no longer has the saved one.
+                let catalog = self.config.craft.catalog();
+                let floss = catalog.find(&saved.floss).unwrap_or_else(|| catalog.closest(color));
+                let blend = saved.blend.as_deref().and_then(|code| catalog.find(code));
+                PaletteColor { color, floss, blend }
+            }).collect(),
+            stitches: self.stitches.clone(),
+
+            // Backstitch is saved with the decorations.
+            backstitches: vec![],
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        std::fs::write(path, serde_json::to_string(self)?)
+    }
+
+    pub fn open(path: &Path) -> io::Result<Project> {
+        let mut project: Project = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        if project.version > PROJECT_VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                format!("project version {} is newer than supported version {}", project.version, PROJECT_VERSION)));
+        }
+
+        let stitch_count = project.width * project.height;
+        if project.stitches.len() != stitch_count || project.stitches.iter().any(|i| *i >= project.palette.len()) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "project stitches don't match its size and palette"));
+        }
+
+        // Like presets, projects may have been edited by hand or saved by an older version.
+        project.config.clamp_to_ranges();
+        project.progress.fit_saved(project.width, project.height);
+        if let Some(border) = &mut project.decorations.border {
+            border.margin = border.margin.min(*border::MARGIN_RANGE.end());
+        }
+
+        Ok(project)
+    }
+}
@@ -1,28 +1,698 @@
-use crate::egui::{Color32, Ui};
-use crate::egui::plot::{Legend, MarkerShape, Plot, Points};
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::ops::RangeInclusive;
+use std::path::Path;
 
-use super::analysis::ColorPoint;
+use crate::egui::{self, Align2, Color32, ColorImage, FontId, Pos2, Rect, Response, Sense, Stroke, TextureHandle, TextureOptions, Ui, Vec2};
 
+use super::craft::{Geometry, Offset};
+use super::floss::{self, Catalog, Floss};
+
+/// A color used on the chart, along with the floss it is stitched with.
+#[derive(Clone)]
+pub struct PaletteColor {
+    pub color: Color32,
+    pub floss: &'static Floss,
+
+    // Second floss, when stitched with one strand of each
+    pub blend: Option<&'static Floss>,
+}
+
+impl PaletteColor {
+    pub fn new(color: Color32, floss: &'static Floss) -> Self {
+        PaletteColor { color, floss, blend: None }
+    }
+
+    /// How the stitched color actually looks, which may differ from the chart color.
+    pub fn thread_color(&self) -> Color32 {
+        match self.blend {
+            Some(blend) => floss::blend_color(self.floss, blend),
+            None => self.floss.color(),
+        }
+    }
+
+    /// Each floss this color is stitched with, and the fraction of the strands it makes up.
+    pub fn threads(&self) -> Vec<(&'static Floss, f32)> {
+        match self.blend {
+            Some(blend) => vec![(self.floss, 0.5), (blend, 0.5)],
+            None => vec![(self.floss, 1.0)],
+        }
+    }
+}
+
+impl fmt::Display for PaletteColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.blend {
+            Some(blend) => write!(f, "{} + {}", self.floss, blend),
+            None => write!(f, "{}", self.floss),
+        }
+    }
+}
+
+// Chart symbols, skipping look-alikes such as O/0 and I/l/1.
+// Blends draw from their own set so they stand out from single-floss colors.
+const SYMBOLS: &[char] = &[
+    '+', 'X', '#', '%', '@', '&', '=', '?', '!', '/', '<', '>', '^', '~', '$', '*',
+    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'J', 'K', 'L', 'M', 'N', 'P', 'R', 'S', 'T', 'U', 'V', 'W', 'Y', 'Z',
+    '2', '3', '4', '5', '6', '7', '8', '9',
+];
+const BLEND_SYMBOLS: &[char] = &[
+    'a', 'b', 'd', 'e', 'f', 'g', 'h', 'k', 'm', 'n', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'y', 'z',
+];
+
+// Shared by both once their own set runs out, again skipping anything that looks like an earlier symbol.
+// All of these are in egui's default monospace fonts.
+const EXTRA_SYMBOLS: &[char] = &[
+    'c', 'j', 'x',
+    'Γ', 'Δ', 'Θ', 'Λ', 'Ξ', 'Π', 'Σ', 'Φ', 'Ψ', 'Ω',
+    'α', 'β', 'γ', 'δ', 'ε', 'ζ', 'η', 'θ', 'λ', 'μ', 'ξ', 'π', 'σ', 'ς', 'τ', 'φ', 'ψ', 'ω',
+    'Б', 'Д', 'Ж', 'З', 'И', 'Й', 'Л', 'Ц', 'Ч', 'Ш', 'Щ', 'Ъ', 'Ы', 'Ь', 'Э', 'Ю', 'Я',
+    'в', 'г', 'д', 'ж', 'з', 'и', 'й', 'л', 'ц', 'ч', 'ш', 'щ', 'ъ', 'ы', 'ь', 'э', 'ю', 'я',
+    '§', '¶', '±', '÷', '¤', '£', '¥', '€', '¢', 'Ø', 'ß', 'Þ', 'Æ', 'Œ', '¿', '¡', '«', '»', '©', '®', '°', '¬', '¼', '½', '¾',
+    '←', '↑', '→', '↓', '↔', '↕',
+    '■', '□', '▲', '△', '▼', '▽', '◆', '◇', '○', '●', '◐', '◑',
+    '∞', '≈', '≠', '≤', '≥', '√', '∫', '∩', '∪', '⊕', '⊗',
+];
+
+/// Shown for colors beyond the last symbol, rather than giving two colors the same one.
+pub const NO_SYMBOL: char = ' ';
+
+// Saved chart images are drawn this many pixels per stitch, so they stay crisp when viewed.
+const CHART_IMAGE_SCALE: usize = 8;
+
+// A stitch's length of backstitch, in one strand, takes about a quarter of the floss of a full cross stitch in two.
+const BACKSTITCH_PER_STITCH: f32 = 0.25;
+
+/// A straight backstitch line between two corners of the stitch grid, counted in stitches from the chart's top-left corner.
+#[derive(Clone)]
+pub struct Backstitch {
+    pub from: [usize; 2],
+    pub to: [usize; 2],
+    pub floss: &'static Floss,
+}
+
+impl Backstitch {
+    /// The same line on a chart grown by `inset` stitches on every side.
+    pub fn shifted(&self, inset: usize) -> Backstitch {
+        Backstitch { from: self.from.map(|i| i + inset), to: self.to.map(|i| i + inset), floss: self.floss }
+    }
+}
+
+/// The stitch grid. Stitches are stored row-major as indices into the palette.
+#[derive(Clone)]
 pub struct ChartData {
-    pub points: Vec<ColorPoint>
+    pub width: usize,
+    pub height: usize,
+    pub geometry: Geometry,
+
+    // What the palette's flosses (or yarns or beads) are picked from
+    pub catalog: Catalog,
+    pub palette: Vec<PaletteColor>,
+    pub stitches: Vec<usize>,
+
+    // Lines stitched over the stitches, for outlines and details finer than a stitch. Only square grids have them.
+    pub backstitches: Vec<Backstitch>,
 }
 
-pub fn convert_to_points(point: &ColorPoint) -> Points {
-    Points::new(vec![[point.x, point.y]])
-        .filled(true)
-        .radius(4.0)
-        .color(Color32::from(point.c))
-        .shape(MarkerShape::Square)
+impl ChartData {
+    pub fn stitch(&self, x: usize, y: usize) -> &PaletteColor {
+        &self.palette[self.stitches[x + y * self.width]]
+    }
+
+    /// Symbol for each palette color. Every color gets its own symbol, until even the extra symbols run out
+    /// and the rest get [`NO_SYMBOL`] (see [`Self::colors_without_symbol`]).
+    pub fn symbols(&self) -> Vec<char> {
+        let (mut single, mut blended, mut extra) = (SYMBOLS.iter(), BLEND_SYMBOLS.iter(), EXTRA_SYMBOLS.iter());
+        self.palette.iter().map(|color| {
+            let symbols = if color.blend.is_some() { &mut blended } else { &mut single };
+            symbols.next().or_else(|| extra.next()).copied().unwrap_or(NO_SYMBOL)
+        }).collect()
+    }
+
+    /// Number of palette colors too many for the symbol set, which are charted without a symbol.
+    pub fn colors_without_symbol(&self) -> usize {
+        self.symbols().into_iter().filter(|symbol| *symbol == NO_SYMBOL).count()
+    }
+
+    /// Each floss used, in palette order then backstitch order, with how many full stitches' worth of it the chart needs.
+    /// A blended stitch uses half as much of each of its flosses.
+    pub fn floss_usage(&self) -> Vec<(&'static Floss, f32)> {
+        let mut usage: Vec<(&'static Floss, f32)> = vec![];
+        let mut add = |floss: &'static Floss, amount: f32| match usage.iter_mut().find(|(used, _)| used.code == floss.code) {
+            Some((_, stitches)) => *stitches += amount,
+            None => usage.push((floss, amount)),
+        };
+        for (color, count) in self.palette.iter().zip(self.color_counts()) {
+            for (floss, fraction) in color.threads() {
+                add(floss, fraction * count as f32);
+            }
+        }
+        for line in &self.backstitches {
+            let (dx, dy) = (line.to[0].abs_diff(line.from[0]) as f32 * self.geometry.aspect, line.to[1].abs_diff(line.from[1]) as f32);
+            add(line.floss, (dx * dx + dy * dy).sqrt() * BACKSTITCH_PER_STITCH);
+        }
+
+        usage.retain(|(_, stitches)| *stitches > 0.0);
+        usage
+    }
+
+    /// Number of stitches of each palette color
+    pub fn color_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.palette.len()];
+        for palette_index in &self.stitches {
+            counts[*palette_index] += 1;
+        }
+
+        counts
+    }
+
+    /// Drops palette colors no stitch uses, keeping the rest in order.
+    pub fn remove_unused_colors(&mut self) {
+        let counts = self.color_counts();
+        let mut new_index = vec![usize::MAX; counts.len()];
+        let mut palette = vec![];
+        for (i, color) in self.palette.iter().enumerate().filter(|(i, _)| counts[*i] > 0) {
+            new_index[i] = palette.len();
+            palette.push(color.clone());
+        }
+
+        self.palette = palette;
+        for stitch in self.stitches.iter_mut() {
+            *stitch = new_index[*stitch];
+        }
+    }
+
+    /// Row-major indices of the contiguous (4-connected) block of same-colored stitches containing the given stitch.
+    pub fn region_at(&self, start: usize) -> Vec<usize> {
+        self.unvisited_region_at(start, &mut vec![false; self.stitches.len()])
+    }
+
+    /// As [`Self::region_at`], marking the region's stitches as visited.
+    /// Sharing `visited` across calls makes splitting a whole chart into regions linear time.
+    pub fn unvisited_region_at(&self, start: usize, visited: &mut [bool]) -> Vec<usize> {
+        let palette_index = self.stitches[start];
+        let mut region = vec![];
+        let mut queue = VecDeque::from([start]);
+        visited[start] = true;
+        while let Some(stitch) = queue.pop_front() {
+            region.push(stitch);
+
+            let (x, y) = (stitch % self.width, stitch / self.width);
+            let neighbors = [
+                (x > 0).then(|| stitch - 1),
+                (x + 1 < self.width).then(|| stitch + 1),
+                (y > 0).then(|| stitch - self.width),
+                (y + 1 < self.height).then(|| stitch + self.width),
+            ];
+            for neighbor in neighbors.into_iter().flatten() {
+                if !visited[neighbor] && self.stitches[neighbor] == palette_index {
+                    visited[neighbor] = true;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        region.sort_unstable();
+        region
+    }
+
+    /// One pixel per stitch, so the whole chart can be drawn as a single texture.
+    pub fn to_image(&self) -> ColorImage {
+        ColorImage {
+            size: [self.width, self.height],
+            pixels: self.stitches.iter().map(|i| self.palette[*i].color).collect(),
+        }
+    }
+
+    /// Saves the chart image, enlarged to [`CHART_IMAGE_SCALE`] pixels per stitch, in the format of the file extension.
+    pub fn save_image(&self, path: &Path) -> io::Result<()> {
+        let chart_image = self.to_image();
+        let (width, height) = (self.width * CHART_IMAGE_SCALE, self.height * CHART_IMAGE_SCALE);
+        let mut bytes = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            for x in 0..width {
+                bytes.extend_from_slice(&chart_image.pixels[x / CHART_IMAGE_SCALE + (y / CHART_IMAGE_SCALE) * self.width].to_array());
+            }
+        }
+
+        image::save_buffer(path, &bytes, width as u32, height as u32, image::ColorType::Rgba8)
+            .map_err(io::Error::other)
+    }
+}
+
+/// Lays out a one-pixel-per-stitch image to match an offset grid, two pixels per stitch along the offset,
+/// so it can still be drawn as a single texture. Gaps at the ragged edges are left transparent.
+fn expand_offset(image: &ColorImage, offset: Offset) -> ColorImage {
+    let [width, height] = image.size;
+    match offset {
+        Offset::None => image.clone(),
+        Offset::Rows => {
+            let mut expanded = ColorImage::new([width * 2 + 1, height], Color32::TRANSPARENT);
+            for y in 0..height {
+                for x in 0..width {
+                    let start = x * 2 + y % 2 + y * expanded.size[0];
+                    expanded.pixels[start..start + 2].fill(image.pixels[x + y * width]);
+                }
+            }
+            expanded
+        },
+        Offset::Columns => {
+            let mut expanded = ColorImage::new([width, height * 2 + 1], Color32::TRANSPARENT);
+            for y in 0..height {
+                for x in 0..width {
+                    let first_row = y * 2 + x % 2;
+                    for row in first_row..first_row + 2 {
+                        expanded.pixels[x + row * width] = image.pixels[x + y * width];
+                    }
+                }
+            }
+            expanded
+        },
+    }
+}
+
+/// Small square of color, for showing a floss next to its name.
+pub fn color_swatch(ui: &mut Ui, color: Color32) {
+    let (swatch, _) = ui.allocate_exact_size(Vec2::splat(12.0), Sense::hover());
+    ui.painter().rect_filled(swatch, 2.0, color);
+}
+
+const RULER_SIZE: f32 = 24.0;
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 64.0;
+
+// Single-stitch grid lines are only drawn once the stitches are large enough to tell apart.
+const MINOR_GRID_ZOOM: f32 = 6.0;
+const SYMBOL_ZOOM: f32 = 14.0;
+const LABEL_ZOOM: f32 = 8.0;
+pub const MAJOR_GRID_SPACING: usize = 10;
+
+// Minimum screen distance between ruler labels, so they don't overlap when zoomed out.
+const RULER_LABEL_SPACING: f32 = 30.0;
+
+/// Size of the whole chart, in cell heights.
+fn chart_size(chart_data: &ChartData) -> Vec2 {
+    let geometry = chart_data.geometry;
+    let overhang = |offset| if geometry.offset == offset { 0.5 } else { 0.0 };
+    Vec2::new(
+        (chart_data.width as f32 + overhang(Offset::Rows)) * geometry.aspect,
+        chart_data.height as f32 + overhang(Offset::Columns))
 }
 
-pub fn render_chart(ui: &mut Ui, chart_data: &ChartData) {
-    let markers_plot = Plot::new("cross_pattern")
-        .data_aspect(1.0)
-        .legend(Legend::default());
+/// Where the chart landed on screen this frame, and which of its lines are visible.
+struct View {
+    area: Rect,
+    chart_rect: Rect,
+    geometry: Geometry,
+
+    // Screen points per cell height
+    zoom: f32,
+    columns: RangeInclusive<usize>,
+    rows: RangeInclusive<usize>,
+}
+
+impl View {
+    fn cell_size(&self) -> Vec2 {
+        Vec2::new(self.zoom * self.geometry.aspect, self.zoom)
+    }
+
+    fn column_x(&self, x: usize) -> f32 {
+        self.chart_rect.min.x + x as f32 * self.cell_size().x
+    }
+
+    fn row_y(&self, y: usize) -> f32 {
+        self.chart_rect.min.y + y as f32 * self.zoom
+    }
+
+    /// Half a cell for the odd rows or columns of an offset grid.
+    fn shift(&self, i: usize, offset: Offset) -> f32 {
+        if self.geometry.offset == offset && i % 2 == 1 { 0.5 } else { 0.0 }
+    }
+
+    fn cell_rect(&self, x: usize, y: usize) -> Rect {
+        let cell_size = self.cell_size();
+        let min = Pos2::new(
+            self.column_x(x) + self.shift(y, Offset::Rows) * cell_size.x,
+            self.row_y(y) + self.shift(x, Offset::Columns) * cell_size.y);
+        Rect::from_min_size(min, cell_size)
+    }
+
+    fn corner_pos(&self, [x, y]: [usize; 2]) -> Pos2 {
+        Pos2::new(self.column_x(x), self.row_y(y))
+    }
 
-    markers_plot.show(ui, |plot_ui| {
-        for point in &chart_data.points {
-            plot_ui.points(convert_to_points(point));
+    /// The grid corner nearest a screen position over the chart, if the grid has corners to stitch between.
+    fn corner_at(&self, pos: Pos2, chart_data: &ChartData) -> Option<[usize; 2]> {
+        if self.geometry.offset != Offset::None || !self.area.contains(pos) || !self.chart_rect.expand(self.zoom / 2.0).contains(pos) {
+            return None;
         }
-    });
-}
\ No newline at end of file
+
+        let corners = ((pos - self.chart_rect.min) / self.cell_size()).round();
+        Some([(corners.x.max(0.0) as usize).min(chart_data.width), (corners.y.max(0.0) as usize).min(chart_data.height)])
+    }
+
+    /// The stitch under a screen position, if any.
+    fn stitch_at(&self, pos: Pos2, chart_data: &ChartData) -> Option<(usize, usize)> {
+        if !self.area.contains(pos) || !self.chart_rect.contains(pos) {
+            return None;
+        }
+
+        // In cells from the chart's top-left corner
+        let cells = (pos - self.chart_rect.min) / self.cell_size();
+        let (x, y) = match self.geometry.offset {
+            Offset::Rows => {
+                let y = cells.y as usize;
+                ((cells.x - self.shift(y, Offset::Rows)).max(0.0) as usize, y)
+            },
+            Offset::Columns => {
+                let x = cells.x as usize;
+                (x, (cells.y - self.shift(x, Offset::Columns)).max(0.0) as usize)
+            },
+            Offset::None => (cells.x as usize, cells.y as usize),
+        };
+
+        Some((x.min(chart_data.width - 1), y.min(chart_data.height - 1)))
+    }
+}
+
+pub struct ChartResponse {
+    pub response: Response,
+
+    // Row-major index of the stitch under the pointer
+    pub hovered_stitch: Option<usize>,
+
+    // Grid corner nearest the pointer, for drawing backstitch
+    pub hovered_corner: Option<[usize; 2]>,
+}
+
+/// Zoomable, pannable view of a [`ChartData`].
+/// Scroll (or pinch) to zoom, drag to pan, double-click to fit the chart to the view.
+pub struct Chart {
+    texture: Option<TextureHandle>,
+
+    // Symbol of each palette color, worked out with the texture rather than on every frame
+    symbols: Option<Vec<char>>,
+
+    // Stitch-sized images drawn over the chart, in the order they were added.
+    // Textures are made on the next draw, once the chart's geometry is known.
+    overlays: Vec<(&'static str, ColorImage, Option<TextureHandle>)>,
+
+    // Text drawn at stitches over everything else, such as step numbers
+    labels: Vec<(usize, String)>,
+
+    // A backstitch line being drawn, between grid corners
+    preview_line: Option<([usize; 2], [usize; 2], Color32)>,
+
+    // Screen points per cell height. Zero until the chart has been fit to the view.
+    zoom: f32,
+
+    // Position of the chart's top-left corner, relative to the top-left of the chart area.
+    offset: Vec2,
+}
+
+impl Default for Chart {
+    fn default() -> Self {
+        Self {
+            texture: None,
+            symbols: None,
+            overlays: vec![],
+            labels: vec![],
+            preview_line: None,
+            zoom: 0.0,
+            offset: Vec2::ZERO,
+        }
+    }
+}
+
+impl Chart {
+    /// Drops the cached texture and symbols so they are rebuilt from new chart data on the next draw.
+    /// Overlays and labels are dropped too, as they were made for the old chart.
+    pub fn invalidate(&mut self) {
+        self.texture = None;
+        self.symbols = None;
+        self.overlays.clear();
+        self.labels.clear();
+    }
+
+    /// Replaces the labels, each at a row-major stitch index.
+    pub fn set_labels(&mut self, labels: Vec<(usize, String)>) {
+        self.labels = labels;
+    }
+
+    /// Sets or clears the backstitch line being drawn.
+    pub fn set_preview_line(&mut self, line: Option<([usize; 2], [usize; 2], Color32)>) {
+        self.preview_line = line;
+    }
+
+    /// Sets or clears a named overlay. The image must have one pixel per stitch.
+    pub fn set_overlay(&mut self, layer: &'static str, image: Option<ColorImage>) {
+        let Some(image) = image else {
+            self.overlays.retain(|(name, _, _)| *name != layer);
+            return;
+        };
+
+        match self.overlays.iter_mut().find(|(name, _, _)| *name == layer) {
+            Some((_, overlay, texture)) => {
+                *overlay = image;
+                *texture = None;
+            },
+            None => self.overlays.push((layer, image, None)),
+        }
+    }
+
+    /// Fits the chart to the view the next time it is drawn.
+    pub fn reset_view(&mut self) {
+        self.zoom = 0.0;
+    }
+
+    pub fn show(&mut self, ui: &mut Ui, chart_data: &ChartData) -> ChartResponse {
+        let (mut response, painter) = ui.allocate_painter(ui.available_size(), Sense::click_and_drag());
+        let area = Rect::from_min_max(response.rect.min + Vec2::splat(RULER_SIZE), response.rect.max);
+        let chart_size = chart_size(chart_data);
+
+        if chart_data.width == 0 || chart_data.height == 0 {
+            return ChartResponse { response, hovered_stitch: None, hovered_corner: None };
+        }
+
+        if self.zoom == 0.0 || response.double_clicked() {
+            self.zoom = (area.width() / chart_size.x).min(area.height() / chart_size.y).clamp(MIN_ZOOM, MAX_ZOOM);
+            self.offset = (area.size() - chart_size * self.zoom) / 2.0;
+        }
+
+        if response.dragged() {
+            self.offset += response.drag_delta();
+        }
+
+        if let Some(hover_pos) = response.hover_pos() {
+            let (scroll, pinch) = ui.input(|i| (i.scroll_delta.y, i.zoom_delta()));
+            let new_zoom = (self.zoom * pinch * (scroll * 0.002).exp()).clamp(MIN_ZOOM, MAX_ZOOM);
+            if new_zoom != self.zoom {
+                // Zoom around the pointer, so the hovered stitch stays put.
+                let anchor = hover_pos - area.min;
+                self.offset = anchor - (anchor - self.offset) * (new_zoom / self.zoom);
+                self.zoom = new_zoom;
+            }
+        }
+
+        let chart_rect = Rect::from_min_size(area.min + self.offset, chart_size * self.zoom);
+        let chart_painter = painter.with_clip_rect(area);
+
+        let offset = chart_data.geometry.offset;
+        let texture = self.texture.get_or_insert_with(|| {
+            ui.ctx().load_texture("cross-chart", expand_offset(&chart_data.to_image(), offset), TextureOptions::NEAREST)
+        });
+        let full_uv = Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0));
+        chart_painter.image(texture.id(), chart_rect, full_uv, Color32::WHITE);
+        for (layer, image, overlay) in &mut self.overlays {
+            let overlay = overlay.get_or_insert_with(|| {
+                ui.ctx().load_texture(format!("cross-chart-{}", layer), expand_offset(image, offset), TextureOptions::NEAREST)
+            });
+            chart_painter.image(overlay.id(), chart_rect, full_uv, Color32::WHITE);
+        }
+
+        // Only the visible stitch range needs grid lines and ruler labels.
+        let cell_width = self.zoom * chart_data.geometry.aspect;
+        let first_x = ((area.min.x - chart_rect.min.x) / cell_width).floor().max(0.0) as usize;
+        let last_x = (((area.max.x - chart_rect.min.x) / cell_width).ceil().max(0.0) as usize).min(chart_data.width);
+        let first_y = ((area.min.y - chart_rect.min.y) / self.zoom).floor().max(0.0) as usize;
+        let last_y = (((area.max.y - chart_rect.min.y) / self.zoom).ceil().max(0.0) as usize).min(chart_data.height);
+        let view = View {
+            area,
+            chart_rect,
+            geometry: chart_data.geometry,
+            zoom: self.zoom,
+            columns: first_x.min(last_x)..=last_x,
+            rows: first_y.min(last_y)..=last_y,
+        };
+
+        let symbols = self.symbols.get_or_insert_with(|| chart_data.symbols());
+        draw_symbols(&chart_painter, &view, chart_data, symbols);
+        draw_grid(&chart_painter, &view, chart_data);
+        draw_backstitches(&chart_painter, &view, chart_data, self.preview_line);
+        draw_labels(&chart_painter, &view, chart_data, &self.labels);
+        draw_rulers(ui, &painter, response.rect, &view);
+
+        let hovered = response.hover_pos().and_then(|pos| view.stitch_at(pos, chart_data));
+        if let Some((x, y)) = hovered {
+            chart_painter.rect_stroke(view.cell_rect(x, y), 0.0, Stroke::new(2.0, Color32::WHITE));
+
+            let stitch = chart_data.stitch(x, y);
+            let symbol = symbols[chart_data.stitches[x + y * chart_data.width]];
+            response = response.on_hover_ui_at_pointer(|ui| {
+                ui.label(format!("Column {}, Row {}", x + 1, y + 1));
+                ui.horizontal(|ui| {
+                    color_swatch(ui, stitch.color);
+                    ui.label(format!("{} {}", symbol, stitch));
+                });
+            });
+        }
+
+        let hovered_corner = response.hover_pos().and_then(|pos| view.corner_at(pos, chart_data));
+        ChartResponse {
+            response,
+            hovered_stitch: hovered.map(|(x, y)| x + y * chart_data.width),
+            hovered_corner,
+        }
+    }
+}
+
+/// Dark symbols on light stitches and vice versa
+pub fn symbol_color(color: Color32) -> Color32 {
+    let luma = 0.299 * color.r() as f32 + 0.587 * color.g() as f32 + 0.114 * color.b() as f32;
+    if luma > 128.0 { Color32::BLACK } else { Color32::WHITE }
+}
+
+fn draw_symbols(painter: &egui::Painter, view: &View, chart_data: &ChartData, symbols: &[char]) {
+    if view.zoom < SYMBOL_ZOOM {
+        return;
+    }
+
+    let font = FontId::monospace(view.cell_size().min_elem() * 0.7);
+    for y in view.rows.clone().filter(|y| *y < chart_data.height) {
+        for x in view.columns.clone().filter(|x| *x < chart_data.width) {
+            let palette_index = chart_data.stitches[x + y * chart_data.width];
+
+            let text_color = symbol_color(chart_data.palette[palette_index].color);
+            painter.text(view.cell_rect(x, y).center(), Align2::CENTER_CENTER, symbols[palette_index], font.clone(), text_color);
+        }
+    }
+}
+
+/// Draws backstitch in its floss colors, edged in the opposite of their brightness so light lines show on light stitches.
+fn draw_backstitches(painter: &egui::Painter, view: &View, chart_data: &ChartData, preview_line: Option<([usize; 2], [usize; 2], Color32)>) {
+    let width = (view.zoom * 0.15).max(2.0);
+    let lines = chart_data.backstitches.iter().map(|line| (line.from, line.to, line.floss.color())).chain(preview_line);
+    for (from, to, color) in lines {
+        let points = [view.corner_pos(from), view.corner_pos(to)];
+        painter.line_segment(points, Stroke::new(width + 2.0, symbol_color(color).linear_multiply(0.6)));
+        painter.line_segment(points, Stroke::new(width, color));
+    }
+}
+
+fn draw_labels(painter: &egui::Painter, view: &View, chart_data: &ChartData, labels: &[(usize, String)]) {
+    if view.zoom < LABEL_ZOOM {
+        return;
+    }
+
+    let font = FontId::proportional(view.zoom.min(SYMBOL_ZOOM) * 0.8);
+    for (stitch, text) in labels {
+        let (x, y) = (stitch % chart_data.width, stitch / chart_data.width);
+        if !view.columns.contains(&x) || !view.rows.contains(&y) {
+            continue;
+        }
+
+        // Dark backing, so labels read over any stitch color
+        let anchor = view.cell_rect(x, y).left_top();
+        let galley = painter.layout_no_wrap(text.clone(), font.clone(), Color32::WHITE);
+        painter.rect_filled(Rect::from_min_size(anchor, galley.size()).expand(1.0), 2.0, Color32::from_black_alpha(200));
+        painter.galley(anchor, galley);
+    }
+}
+
+fn draw_grid(painter: &egui::Painter, view: &View, chart_data: &ChartData) {
+    let major = Stroke::new(1.0, Color32::from_black_alpha(200));
+    let minor = Stroke::new(0.5, Color32::from_black_alpha(80));
+    let zoomed_in = view.zoom >= MINOR_GRID_ZOOM;
+
+    // Offset grids don't line up into straight lines, so each bead is outlined instead.
+    if view.geometry.offset != Offset::None {
+        if zoomed_in {
+            for y in view.rows.clone().filter(|y| *y < chart_data.height) {
+                for x in view.columns.clone().filter(|x| *x < chart_data.width) {
+                    painter.rect_stroke(view.cell_rect(x, y), view.zoom * 0.2, minor);
+                }
+            }
+        }
+        return;
+    }
+
+    // Drills are outlined individually, within the usual major grid.
+    let draw_minor = zoomed_in && !view.geometry.round_cells;
+    if zoomed_in && view.geometry.round_cells {
+        for y in view.rows.clone().filter(|y| *y < chart_data.height) {
+            for x in view.columns.clone().filter(|x| *x < chart_data.width) {
+                let cell = view.cell_rect(x, y);
+                painter.circle_stroke(cell.center(), cell.size().min_elem() * 0.45, minor);
+            }
+        }
+    }
+
+    let line_stroke = |i: usize, count: usize| {
+        if i.is_multiple_of(MAJOR_GRID_SPACING) || i == count {
+            Some(major)
+        } else if draw_minor {
+            Some(minor)
+        } else {
+            None
+        }
+    };
+
+    for x in view.columns.clone() {
+        if let Some(stroke) = line_stroke(x, chart_data.width) {
+            painter.vline(view.column_x(x), view.chart_rect.y_range(), stroke);
+        }
+    }
+
+    for y in view.rows.clone() {
+        if let Some(stroke) = line_stroke(y, chart_data.height) {
+            painter.hline(view.chart_rect.x_range(), view.row_y(y), stroke);
+        }
+    }
+}
+
+fn draw_rulers(ui: &Ui, painter: &egui::Painter, full_rect: Rect, view: &View) {
+    let area = view.area;
+    let top = Rect::from_min_max(Pos2::new(area.min.x, full_rect.min.y), Pos2::new(area.max.x, area.min.y));
+    let left = Rect::from_min_max(Pos2::new(full_rect.min.x, area.min.y), Pos2::new(area.min.x, area.max.y));
+    let background = ui.visuals().faint_bg_color;
+    let text_color = ui.visuals().text_color();
+    let tick = Stroke::new(1.0, text_color);
+    let font = FontId::monospace(10.0);
+
+    painter.rect_filled(top, 0.0, background);
+    painter.rect_filled(left, 0.0, background);
+
+    // Label every 10 stitches, backing off to every 20, 50, 100... as the chart shrinks.
+    let mut label_step = MAJOR_GRID_SPACING;
+    for multiple in [2, 5, 10, 20, 50] {
+        if (label_step as f32) * view.zoom >= RULER_LABEL_SPACING {
+            break;
+        }
+        label_step = MAJOR_GRID_SPACING * multiple;
+    }
+
+    let top_painter = painter.with_clip_rect(top);
+    for x in view.columns.clone().filter(|x| *x > 0 && x % label_step == 0) {
+        let screen_x = view.column_x(x);
+        top_painter.vline(screen_x, (top.max.y - 6.0)..=top.max.y, tick);
+        top_painter.text(Pos2::new(screen_x, top.max.y - 6.0), Align2::CENTER_BOTTOM, x.to_string(), font.clone(), text_color);
+    }
+
+    let left_painter = painter.with_clip_rect(left);
+    for y in view.rows.clone().filter(|y| *y > 0 && y % label_step == 0) {
+        let screen_y = view.row_y(y);
+        left_painter.hline((left.max.x - 6.0)..=left.max.x, screen_y, tick);
+        left_painter.text(Pos2::new(left.max.x - 7.0, screen_y), Align2::RIGHT_CENTER, y.to_string(), font.clone(), text_color);
+    }
+}
@@ -0,0 +1,143 @@
+//! Repeats or mirrors one tile of the chart across the rest of it, for borders, bookmarks and fabric repeats
+use crate::egui::{Color32, ColorImage};
+
+use serde::{Deserialize, Serialize};
+
+use super::renderer::ChartData;
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Symmetry {
+    RepeatAcross,
+    RepeatDown,
+    RepeatBoth,
+    // Mirrored left to right about the middle of the chart
+    Mirror2,
+    // Also mirrored top to bottom
+    Mirror4,
+    // Also mirrored about the diagonals through the middle of the chart
+    Mirror8,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 6] = [Symmetry::RepeatAcross, Symmetry::RepeatDown, Symmetry::RepeatBoth, Symmetry::Mirror2, Symmetry::Mirror4, Symmetry::Mirror8];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Symmetry::RepeatAcross => "Repeat across",
+            Symmetry::RepeatDown => "Repeat down",
+            Symmetry::RepeatBoth => "Repeat across and down",
+            Symmetry::Mirror2 => "2-way mirror",
+            Symmetry::Mirror4 => "4-way mirror",
+            Symmetry::Mirror8 => "8-way mirror",
+        }
+    }
+}
+
+/// Rectangle of stitches, on the analyzed chart.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Tile {
+    /// The tile with the two given stitches at opposite corners.
+    pub fn between(chart_data: &ChartData, a: usize, b: usize) -> Tile {
+        let (ax, ay) = (a % chart_data.width, a / chart_data.width);
+        let (bx, by) = (b % chart_data.width, b / chart_data.width);
+        Tile { x: ax.min(bx), y: ay.min(by), width: ax.abs_diff(bx) + 1, height: ay.abs_diff(by) + 1 }
+    }
+
+    fn contains(&self, x: usize, y: usize) -> bool {
+        (self.x..self.x + self.width).contains(&x) && (self.y..self.y + self.height).contains(&y)
+    }
+
+    /// Tints the tile's stitches, for drawing over the chart.
+    pub fn overlay(&self, chart_data: &ChartData) -> ColorImage {
+        let tint = Color32::from_rgba_unmultiplied(0, 200, 255, 90);
+        ColorImage {
+            size: [chart_data.width, chart_data.height],
+            pixels: (0..chart_data.stitches.len())
+                .map(|stitch| if self.contains(stitch % chart_data.width, stitch / chart_data.width) { tint } else { Color32::TRANSPARENT })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Repeat {
+    pub tile: Tile,
+    pub symmetry: Symmetry,
+}
+
+/// Position of `value` within repeats of `length` starting at `start`
+fn wrap(value: usize, start: usize, length: usize) -> usize {
+    start + (value as i64 - start as i64).rem_euclid(length as i64) as usize
+}
+
+impl Repeat {
+    /// Copies the tile over the chart. The tile itself is never overwritten,
+    /// so it can be edited (or reanalyzed) and every copy follows.
+    pub fn apply(&self, chart_data: &mut ChartData) {
+        let (width, height) = (chart_data.width, chart_data.height);
+        let tile = Tile {
+            x: self.tile.x.min(width - 1),
+            y: self.tile.y.min(height - 1),
+            width: self.tile.width.min(width - self.tile.x.min(width - 1)).max(1),
+            height: self.tile.height.min(height - self.tile.y.min(height - 1)).max(1),
+        };
+        let source = chart_data.stitches.clone();
+
+        match self.symmetry {
+            Symmetry::RepeatAcross | Symmetry::RepeatDown | Symmetry::RepeatBoth => {
+                let across = self.symmetry != Symmetry::RepeatDown;
+                let down = self.symmetry != Symmetry::RepeatAcross;
+                for y in 0..height {
+                    for x in 0..width {
+                        let in_rows = (tile.y..tile.y + tile.height).contains(&y);
+                        let in_columns = (tile.x..tile.x + tile.width).contains(&x);
+                        if (across || in_columns) && (down || in_rows) {
+                            let source_x = if across { wrap(x, tile.x, tile.width) } else { x };
+                            let source_y = if down { wrap(y, tile.y, tile.height) } else { y };
+                            chart_data.stitches[x + y * width] = source[source_x + source_y * width];
+                        }
+                    }
+                }
+            },
+            Symmetry::Mirror2 | Symmetry::Mirror4 | Symmetry::Mirror8 => {
+                // Positions are doubled and measured from the middle of the chart, so mirroring is just negation.
+                let to_centered = |x: usize, y: usize| (2 * x as i64 - (width as i64 - 1), 2 * y as i64 - (height as i64 - 1));
+                let from_centered = |cx: i64, cy: i64| {
+                    let (x, y) = (cx + width as i64 - 1, cy + height as i64 - 1);
+                    let fits = x >= 0 && y >= 0 && x % 2 == 0 && y % 2 == 0 && ((x / 2) as usize) < width && ((y / 2) as usize) < height;
+                    fits.then_some(((x / 2) as usize, (y / 2) as usize))
+                };
+
+                // Each mirror image as whether it swaps the axes, then the signs of x and y
+                let mut images = vec![(false, -1, 1)];
+                if self.symmetry != Symmetry::Mirror2 {
+                    images.extend([(false, 1, -1), (false, -1, -1)]);
+                }
+                if self.symmetry == Symmetry::Mirror8 {
+                    images.extend([(true, 1, 1), (true, -1, 1), (true, 1, -1), (true, -1, -1)]);
+                }
+
+                for y in tile.y..tile.y + tile.height {
+                    for x in tile.x..tile.x + tile.width {
+                        let (cx, cy) = to_centered(x, y);
+                        for (swap, sign_x, sign_y) in &images {
+                            let (ix, iy) = if *swap { (cy, cx) } else { (cx, cy) };
+                            if let Some((image_x, image_y)) = from_centered(ix * sign_x, iy * sign_y) {
+                                if !tile.contains(image_x, image_y) {
+                                    chart_data.stitches[image_x + image_y * width] = source[x + y * width];
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+        }
+    }
+}
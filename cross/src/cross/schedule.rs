@@ -0,0 +1,158 @@
+//! Splits a chart into daily work packages, keeping each day to as few colors and areas as possible
+use crate::egui::{Color32, ColorImage};
+
+use std::fmt::Write;
+
+use super::renderer::ChartData;
+
+/// Stitches of one region of a single color, worked in one day.
+pub struct Task {
+    pub palette_index: usize,
+
+    // Row-major stitch indices
+    pub stitches: Vec<usize>,
+}
+
+impl Task {
+    /// Inclusive (first column, first row, last column, last row) of the task, zero-based.
+    pub fn bounds(&self, width: usize) -> (usize, usize, usize, usize) {
+        let mut bounds = (usize::MAX, usize::MAX, 0, 0);
+        for stitch in &self.stitches {
+            let (x, y) = (stitch % width, stitch / width);
+            bounds = (bounds.0.min(x), bounds.1.min(y), bounds.2.max(x), bounds.3.max(y));
+        }
+
+        bounds
+    }
+
+    pub fn describe(&self, chart_data: &ChartData) -> String {
+        let (min_x, min_y, max_x, max_y) = self.bounds(chart_data.width);
+        format!("{}: {} stitches, columns {}-{}, rows {}-{}",
+            chart_data.palette[self.palette_index],
+            self.stitches.len(),
+            min_x + 1, max_x + 1,
+            min_y + 1, max_y + 1)
+    }
+}
+
+pub struct Day {
+    pub tasks: Vec<Task>,
+}
+
+impl Day {
+    pub fn stitch_count(&self) -> usize {
+        self.tasks.iter().map(|task| task.stitches.len()).sum()
+    }
+
+    fn add(&mut self, palette_index: usize, stitches: &[usize]) {
+        // One task per region (or part of a region), so its bounds describe where the stitches actually are.
+        // Regions are planned color by color, so a day's tasks still come grouped by color.
+        self.tasks.push(Task { palette_index, stitches: stitches.to_vec() });
+    }
+}
+
+pub struct Schedule {
+    pub stitches_per_day: usize,
+    pub days: Vec<Day>,
+}
+
+/// Contiguous (4-connected) area of a single color.
+struct Region {
+    palette_index: usize,
+    stitches: Vec<usize>,
+}
+
+/// Plans about `num_days` days of stitching.
+/// Work is ordered color by color, and within a color region by region, top to bottom.
+/// Regions larger than what's left of a day are split across days along rows.
+pub fn plan(chart_data: &ChartData, num_days: i32) -> Schedule {
+    let total_stitches = chart_data.stitches.len();
+    let stitches_per_day = ((total_stitches as f64 / num_days.max(1) as f64).ceil() as usize).max(1);
+
+    let mut days = vec![];
+    let mut today = Day { tasks: vec![] };
+    let mut remaining_today = stitches_per_day;
+    for region in find_regions(chart_data) {
+        let mut stitches = &region.stitches[..];
+        while !stitches.is_empty() {
+            let taken = remaining_today.min(stitches.len());
+            today.add(region.palette_index, &stitches[..taken]);
+            stitches = &stitches[taken..];
+            remaining_today -= taken;
+
+            if remaining_today == 0 {
+                days.push(std::mem::replace(&mut today, Day { tasks: vec![] }));
+                remaining_today = stitches_per_day;
+            }
+        }
+    }
+
+    if !today.tasks.is_empty() {
+        days.push(today);
+    }
+
+    Schedule { stitches_per_day, days }
+}
+
+fn find_regions(chart_data: &ChartData) -> Vec<Region> {
+    let mut visited = vec![false; chart_data.stitches.len()];
+    let mut regions = vec![];
+
+    // Row-major scan, so regions come out top to bottom.
+    for start in 0..chart_data.stitches.len() {
+        if visited[start] {
+            continue;
+        }
+
+        // Sorted, so splitting a region across days cuts it along rows.
+        let stitches = chart_data.unvisited_region_at(start, &mut visited);
+        regions.push(Region { palette_index: chart_data.stitches[start], stitches });
+    }
+
+    // Stable sort keeps the top-to-bottom order within each color.
+    // Colors are worked in the order they first appear on the chart.
+    let mut color_order = vec![usize::MAX; chart_data.palette.len()];
+    let mut next = 0;
+    for region in &regions {
+        if color_order[region.palette_index] == usize::MAX {
+            color_order[region.palette_index] = next;
+            next += 1;
+        }
+    }
+    regions.sort_by_key(|region| color_order[region.palette_index]);
+
+    regions
+}
+
+impl Schedule {
+    /// Dims everything outside the given day, for drawing over the chart.
+    pub fn day_overlay(&self, chart_data: &ChartData, day: usize) -> ColorImage {
+        let mut image = ColorImage::new([chart_data.width, chart_data.height], Color32::from_black_alpha(170));
+        for task in &self.days[day].tasks {
+            for stitch in &task.stitches {
+                image.pixels[*stitch] = Color32::TRANSPARENT;
+            }
+        }
+
+        image
+    }
+
+    /// Markdown checklist of every day's work.
+    pub fn to_checklist(&self, chart_data: &ChartData) -> String {
+        let mut checklist = String::new();
+        _ = writeln!(checklist, "# Stitching schedule");
+        _ = writeln!(checklist);
+        _ = writeln!(checklist, "{} stitches over {} days, about {} per day.",
+            chart_data.stitches.len(), self.days.len(), self.stitches_per_day);
+
+        for (i, day) in self.days.iter().enumerate() {
+            _ = writeln!(checklist);
+            _ = writeln!(checklist, "## Day {} ({} stitches)", i + 1, day.stitch_count());
+            for task in &day.tasks {
+                _ = writeln!(checklist, "- [ ] {}", task.describe(chart_data));
+            }
+        }
+
+        checklist
+    }
+}
@@ -0,0 +1,67 @@
+//! The threads on hand, and how much of each is left
+use std::fmt::Write;
+use std::io;
+use std::path::Path;
+
+use super::floss::{Catalog, Floss};
+
+pub struct StashThread {
+    pub floss: &'static Floss,
+
+    // Approximate length of six-strand floss remaining
+    pub meters: f32,
+}
+
+#[derive(Default)]
+pub struct Stash {
+    // Kept in the order threads were added, one entry per floss
+    pub threads: Vec<StashThread>,
+}
+
+impl Stash {
+    /// Adds a floss with the given length, or adds to the length already on hand.
+    pub fn add(&mut self, floss: &'static Floss, meters: f32) {
+        match self.threads.iter_mut().find(|thread| thread.floss.code == floss.code) {
+            Some(thread) => thread.meters += meters,
+            None => self.threads.push(StashThread { floss, meters }),
+        }
+    }
+
+    pub fn meters_of(&self, floss: &Floss) -> Option<f32> {
+        self.threads.iter().find(|thread| thread.floss.code == floss.code).map(|thread| thread.meters)
+    }
+
+    pub fn flosses(&self) -> Vec<&'static Floss> {
+        self.threads.iter().map(|thread| thread.floss).collect()
+    }
+
+    /// Reads a `code,meters` CSV file. A header row and unknown floss codes are skipped.
+    pub fn load(path: &Path) -> io::Result<Stash> {
+        let mut stash = Stash::default();
+        for line in std::fs::read_to_string(path)?.lines() {
+            let mut fields = line.split(',').map(str::trim);
+            let (Some(code), Some(meters)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            if code == "code" {
+                continue;
+            }
+
+            match (Catalog::Dmc.find(code), meters.parse::<f32>()) {
+                (Some(floss), Ok(meters)) => stash.add(floss, meters),
+                _ => print!("Skipping stash entry: {}", line),
+            }
+        }
+
+        Ok(stash)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut csv = String::from("code,meters\n");
+        for thread in &self.threads {
+            _ = writeln!(csv, "{},{}", thread.floss.code, thread.meters);
+        }
+
+        std::fs::write(path, csv)
+    }
+}
@@ -0,0 +1,210 @@
+//! Editable vector charts. Each part of the chart is its own Inkscape layer, and each color its own group within them.
+use crate::egui::Color32;
+
+use std::fmt::Write;
+
+use super::craft::Offset;
+use super::floss::Floss;
+use super::renderer::{self, ChartData, MAJOR_GRID_SPACING};
+
+// Layout, in SVG user units
+const CELL: f32 = 10.0;
+const MARGIN: f32 = 30.0;
+const LEGEND_GAP: f32 = 20.0;
+const LEGEND_ROW: f32 = 16.0;
+const LEGEND_MIN_WIDTH: f32 = 300.0;
+
+fn hex(color: Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+struct Layout {
+    cell_width: f32,
+    offset: Offset,
+}
+
+impl Layout {
+    /// Top-left corner of a stitch
+    fn cell(&self, x: usize, y: usize) -> (f32, f32) {
+        let shift_x = if self.offset == Offset::Rows && y % 2 == 1 { self.cell_width / 2.0 } else { 0.0 };
+        let shift_y = if self.offset == Offset::Columns && x % 2 == 1 { CELL / 2.0 } else { 0.0 };
+        (MARGIN + x as f32 * self.cell_width + shift_x, MARGIN + y as f32 * CELL + shift_y)
+    }
+}
+
+fn open_layer(svg: &mut String, id: &str, label: &str, attributes: &str) {
+    _ = writeln!(svg, r#"<g id="{}" inkscape:groupmode="layer" inkscape:label="{}" {}>"#, id, label, attributes);
+}
+
+/// The chart as an SVG document, with colors, symbols, grid, rulers, backstitch and a legend.
+pub fn to_svg(chart_data: &ChartData) -> String {
+    let layout = Layout { cell_width: CELL * chart_data.geometry.aspect, offset: chart_data.geometry.offset };
+    let half_cell = match layout.offset {
+        Offset::None => (0.0, 0.0),
+        Offset::Rows => (layout.cell_width / 2.0, 0.0),
+        Offset::Columns => (0.0, CELL / 2.0),
+    };
+    let chart_width = chart_data.width as f32 * layout.cell_width + half_cell.0;
+    let chart_height = chart_data.height as f32 * CELL + half_cell.1;
+
+    let counts = chart_data.color_counts();
+    let used: Vec<usize> = (0..chart_data.palette.len()).filter(|i| counts[*i] > 0).collect();
+    let symbols = chart_data.symbols();
+    let mut backstitch_flosses: Vec<&Floss> = vec![];
+    for line in &chart_data.backstitches {
+        if !backstitch_flosses.iter().any(|floss| floss.code == line.floss.code) {
+            backstitch_flosses.push(line.floss);
+        }
+    }
+
+    let legend_top = MARGIN + chart_height + LEGEND_GAP;
+    let width = (chart_width + 2.0 * MARGIN).max(LEGEND_MIN_WIDTH);
+    let height = legend_top + (used.len() + backstitch_flosses.len()) as f32 * LEGEND_ROW + MARGIN;
+
+    let mut svg = String::new();
+    _ = writeln!(svg, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    _ = writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#, width, height);
+    _ = writeln!(svg, r#"<rect width="{}" height="{}" fill="white"/>"#, width, height);
+
+    // Colors: one group per palette color. On square grids, runs of a color along a row are merged into one rectangle.
+    open_layer(&mut svg, "colors", "Colors", "");
+    for palette_index in &used {
+        let color = &chart_data.palette[*palette_index];
+        _ = writeln!(svg, r#"<g id="color-{}" inkscape:label="{}" fill="{}">"#, palette_index, escape(&color.to_string()), hex(color.color));
+        for y in 0..chart_data.height {
+            let mut x = 0;
+            while x < chart_data.width {
+                if chart_data.stitches[x + y * chart_data.width] != *palette_index {
+                    x += 1;
+                    continue;
+                }
+
+                let first = x;
+                x += 1;
+                if layout.offset != Offset::Columns {
+                    while x < chart_data.width && chart_data.stitches[x + y * chart_data.width] == *palette_index {
+                        x += 1;
+                    }
+                }
+                let (left, top) = layout.cell(first, y);
+                _ = writeln!(svg, r#"<rect x="{}" y="{}" width="{}" height="{}"/>"#, left, top, (x - first) as f32 * layout.cell_width, CELL);
+            }
+        }
+        _ = writeln!(svg, "</g>");
+    }
+    _ = writeln!(svg, "</g>");
+
+    // Symbols: also grouped per color, so one color's symbols can be restyled together
+    open_layer(&mut svg, "symbols", "Symbols", &format!(r#"font-family="monospace" font-size="{}" text-anchor="middle" dominant-baseline="central""#, CELL * 0.7));
+    for palette_index in &used {
+        let color = &chart_data.palette[*palette_index];
+        _ = writeln!(svg, r#"<g id="symbols-{}" inkscape:label="{}" fill="{}">"#, palette_index, escape(&color.to_string()), hex(renderer::symbol_color(color.color)));
+        let symbol = escape(&symbols[*palette_index].to_string());
+        for (stitch, _) in chart_data.stitches.iter().enumerate().filter(|(_, i)| **i == *palette_index) {
+            let (left, top) = layout.cell(stitch % chart_data.width, stitch / chart_data.width);
+            _ = writeln!(svg, r#"<text x="{}" y="{}">{}</text>"#, left + layout.cell_width / 2.0, top + CELL / 2.0, symbol);
+        }
+        _ = writeln!(svg, "</g>");
+    }
+    _ = writeln!(svg, "</g>");
+
+    // Grid: heavier every 10 stitches. Offset grids don't line up, so each cell is outlined instead.
+    open_layer(&mut svg, "grid", "Grid", r#"fill="none" stroke="black""#);
+    if layout.offset == Offset::None {
+        for x in 0..=chart_data.width {
+            let major = x % MAJOR_GRID_SPACING == 0 || x == chart_data.width;
+            let left = MARGIN + x as f32 * layout.cell_width;
+            _ = writeln!(svg, r#"<line x1="{0}" y1="{1}" x2="{0}" y2="{2}" stroke-width="{3}"/>"#, left, MARGIN, MARGIN + chart_height, if major { 1.0 } else { 0.25 });
+        }
+        for y in 0..=chart_data.height {
+            let major = y % MAJOR_GRID_SPACING == 0 || y == chart_data.height;
+            let top = MARGIN + y as f32 * CELL;
+            _ = writeln!(svg, r#"<line x1="{1}" y1="{0}" x2="{2}" y2="{0}" stroke-width="{3}"/>"#, top, MARGIN, MARGIN + chart_width, if major { 1.0 } else { 0.25 });
+        }
+    } else {
+        for y in 0..chart_data.height {
+            for x in 0..chart_data.width {
+                let (left, top) = layout.cell(x, y);
+                _ = writeln!(svg, r#"<rect x="{}" y="{}" width="{}" height="{}" stroke-width="0.25"/>"#, left, top, layout.cell_width, CELL);
+            }
+        }
+    }
+    _ = writeln!(svg, "</g>");
+
+    // Rulers: stitch numbers every 10 stitches along the top and left
+    open_layer(&mut svg, "rulers", "Rulers", r#"font-family="sans-serif" font-size="8" fill="black""#);
+    for x in (MAJOR_GRID_SPACING..=chart_data.width).step_by(MAJOR_GRID_SPACING) {
+        _ = writeln!(svg, r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#, MARGIN + x as f32 * layout.cell_width, MARGIN - 6.0, x);
+    }
+    for y in (MAJOR_GRID_SPACING..=chart_data.height).step_by(MAJOR_GRID_SPACING) {
+        _ = writeln!(svg, r#"<text x="{}" y="{}" text-anchor="end" dominant-baseline="central">{}</text>"#, MARGIN - 4.0, MARGIN + y as f32 * CELL, y);
+    }
+    _ = writeln!(svg, "</g>");
+
+    // Backstitch: one group per floss, over everything on the chart itself
+    open_layer(&mut svg, "backstitch", "Backstitch", &format!(r#"stroke-width="{}" stroke-linecap="round""#, CELL * 0.2));
+    for floss in &backstitch_flosses {
+        _ = writeln!(svg, r#"<g id="backstitch-{}" inkscape:label="{}" stroke="{}">"#, escape(floss.code), escape(&floss.to_string()), hex(floss.color()));
+        for line in chart_data.backstitches.iter().filter(|line| line.floss.code == floss.code) {
+            _ = writeln!(svg, r#"<line x1="{}" y1="{}" x2="{}" y2="{}"/>"#,
+                MARGIN + line.from[0] as f32 * layout.cell_width, MARGIN + line.from[1] as f32 * CELL,
+                MARGIN + line.to[0] as f32 * layout.cell_width, MARGIN + line.to[1] as f32 * CELL);
+        }
+        _ = writeln!(svg, "</g>");
+    }
+    _ = writeln!(svg, "</g>");
+
+    // Legend: swatch with its symbol, floss and stitch count for each color, then a line of each backstitch floss
+    open_layer(&mut svg, "legend", "Legend", r#"font-family="sans-serif" font-size="10""#);
+    for (row, palette_index) in used.iter().enumerate() {
+        let color = &chart_data.palette[*palette_index];
+        let top = legend_top + row as f32 * LEGEND_ROW;
+        _ = writeln!(svg, r#"<rect x="{}" y="{}" width="12" height="12" fill="{}" stroke="black" stroke-width="0.5"/>"#, MARGIN, top, hex(color.color));
+        _ = writeln!(svg, r#"<text x="{}" y="{}" text-anchor="middle" dominant-baseline="central" font-family="monospace" fill="{}">{}</text>"#,
+            MARGIN + 6.0, top + 6.0, hex(renderer::symbol_color(color.color)), escape(&symbols[*palette_index].to_string()));
+        _ = writeln!(svg, r#"<text x="{}" y="{}" dominant-baseline="central">{}: {} stitches</text>"#,
+            MARGIN + 18.0, top + 6.0, escape(&color.to_string()), counts[*palette_index]);
+    }
+    for (row, floss) in backstitch_flosses.iter().enumerate() {
+        let top = legend_top + (used.len() + row) as f32 * LEGEND_ROW;
+        _ = writeln!(svg, r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="2"/>"#, MARGIN, top + 6.0, MARGIN + 12.0, top + 6.0, hex(floss.color()));
+        _ = writeln!(svg, r#"<text x="{}" y="{}" dominant-baseline="central">{}: backstitch</text>"#, MARGIN + 18.0, top + 6.0, escape(&floss.to_string()));
+    }
+    _ = writeln!(svg, "</g>");
+
+    _ = writeln!(svg, "</svg>");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cross::craft::Geometry;
+    use crate::cross::floss::{Catalog, DMC};
+    use crate::cross::renderer::{Backstitch, PaletteColor};
+
+    #[test]
+    fn backstitch_gets_its_own_layer_and_legend_row() {
+        let floss = Catalog::Dmc.find("310").expect("DMC has 310");
+        let chart_data = ChartData {
+            width: 2,
+            height: 2,
+            geometry: Geometry::default(),
+            catalog: Catalog::Dmc,
+            palette: vec![PaletteColor::new(DMC[0].color(), &DMC[0])],
+            stitches: vec![0; 4],
+            backstitches: vec![Backstitch { from: [0, 0], to: [2, 1], floss }],
+        };
+
+        let svg = to_svg(&chart_data);
+        let layer = &svg[svg.find(r#"<g id="backstitch""#).expect("backstitch layer")..];
+        assert!(layer.starts_with(r#"<g id="backstitch" inkscape:groupmode="layer""#));
+        assert!(layer.contains(&format!(r#"<g id="backstitch-310" inkscape:label="{}" stroke="{}">"#, floss, hex(floss.color()))));
+        assert!(layer.contains(&format!(r#"<line x1="{0}" y1="{0}" x2="{1}" y2="{2}"/>"#, MARGIN, MARGIN + 2.0 * CELL, MARGIN + CELL)));
+        assert!(svg.contains(&format!("{}: backstitch", floss)));
+    }
+}
@@ -0,0 +1,402 @@
+//! Palette files shared with other design tools: GIMP .gpl, Adobe .ase and Procreate .swatches
+use crate::egui::Color32;
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use super::color;
+use super::renderer::ChartData;
+
+// Adobe Swatch Exchange color block. Other blocks, such as group starts and ends, are skipped.
+const ASE_COLOR: u16 = 0x0001;
+
+// Adobe's "normal" (as opposed to global or spot) color type
+const ASE_NORMAL: u16 = 2;
+
+// Procreate palettes are a zip archive holding this one file
+const PROCREATE_ENTRY: &str = "Swatches.json";
+
+/// A named color from a palette file
+#[derive(Clone)]
+pub struct Swatch {
+    pub name: String,
+    pub color: Color32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Gpl,
+    Ase,
+    Procreate,
+}
+
+impl Format {
+    pub const ALL: [Format; 3] = [Format::Gpl, Format::Ase, Format::Procreate];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Gpl => "GIMP",
+            Format::Ase => "Adobe",
+            Format::Procreate => "Procreate",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Gpl => "gpl",
+            Format::Ase => "ase",
+            Format::Procreate => "swatches",
+        }
+    }
+
+    fn from_path(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        Format::ALL.into_iter().find(|format| format.extension() == extension)
+    }
+}
+
+/// The chart's colors, named after their floss. Colors no stitch uses are left out.
+pub fn from_chart(chart_data: &ChartData) -> Vec<Swatch> {
+    chart_data.palette.iter().zip(chart_data.color_counts())
+        .filter(|(_, count)| *count > 0)
+        .map(|(color, _)| Swatch { name: color.to_string(), color: color.color })
+        .collect()
+}
+
+/// Reads a palette file, in the format given by its extension.
+pub fn load(path: &Path) -> io::Result<Vec<Swatch>> {
+    let format = Format::from_path(path).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "unknown palette file extension"))?;
+    let swatches = match format {
+        Format::Gpl => parse_gpl(&std::fs::read_to_string(path)?)?,
+        Format::Ase => parse_ase(&std::fs::read(path)?)?,
+        Format::Procreate => {
+            let mut archive = zip::ZipArchive::new(std::fs::File::open(path)?).map_err(io::Error::other)?;
+            let mut json = String::new();
+            archive.by_name(PROCREATE_ENTRY).map_err(io::Error::other)?.read_to_string(&mut json)?;
+            parse_procreate(&json)?
+        },
+    };
+
+    if swatches.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "palette has no colors"));
+    }
+    Ok(swatches)
+}
+
+/// Writes a palette file, in the format given by its extension.
+pub fn save(path: &Path, name: &str, swatches: &[Swatch]) -> io::Result<()> {
+    let format = Format::from_path(path).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "unknown palette file extension"))?;
+    match format {
+        Format::Gpl => std::fs::write(path, to_gpl(name, swatches)),
+        Format::Ase => std::fs::write(path, to_ase(swatches)),
+        Format::Procreate => {
+            let mut archive = zip::ZipWriter::new(std::fs::File::create(path)?);
+            archive.start_file(PROCREATE_ENTRY, zip::write::FileOptions::default()).map_err(io::Error::other)?;
+            archive.write_all(to_procreate(name, swatches)?.as_bytes())?;
+            archive.finish().map_err(io::Error::other)?;
+            Ok(())
+        },
+    }
+}
+
+// https://developer.gimp.org/core/standards/gpl/
+fn parse_gpl(text: &str) -> io::Result<Vec<Swatch>> {
+    let mut lines = text.lines();
+    if lines.next().map(str::trim) != Some("GIMP Palette") {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not a GIMP palette"));
+    }
+
+    let mut swatches = vec![];
+    for line in lines.map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with("Name:") || line.starts_with("Columns:") {
+            continue;
+        }
+
+        let mut fields = line.split_whitespace();
+        let rgb: Vec<u8> = fields.by_ref().take(3).filter_map(|field| field.parse().ok()).collect();
+        let [r, g, b] = rgb[..] else {
+            print!("Skipping palette entry: {}", line);
+            continue;
+        };
+        let name = fields.collect::<Vec<_>>().join(" ");
+        swatches.push(Swatch { name, color: Color32::from_rgb(r, g, b) });
+    }
+
+    Ok(swatches)
+}
+
+fn to_gpl(name: &str, swatches: &[Swatch]) -> String {
+    let mut gpl = format!("GIMP Palette\nName: {}\nColumns: 8\n#\n", name);
+    for swatch in swatches {
+        _ = writeln!(gpl, "{:3} {:3} {:3}\t{}", swatch.color.r(), swatch.color.g(), swatch.color.b(), swatch.name);
+    }
+
+    gpl
+}
+
+/// Big-endian reads from an .ase file
+struct AseReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> AseReader<'a> {
+    fn take(&mut self, count: usize) -> io::Result<&'a [u8]> {
+        if self.bytes.len() < count {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "palette file is cut short"));
+        }
+
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().expect("took two bytes")))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().expect("took four bytes")))
+    }
+
+    fn f32(&mut self) -> io::Result<f32> {
+        Ok(f32::from_be_bytes(self.take(4)?.try_into().expect("took four bytes")))
+    }
+}
+
+// http://www.selapa.net/swatches/colors/fileformats.php#adobe_ase
+fn parse_ase(bytes: &[u8]) -> io::Result<Vec<Swatch>> {
+    let mut file = AseReader { bytes };
+    if file.take(4)? != b"ASEF" {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not an Adobe swatch exchange file"));
+    }
+    let _version = (file.u16()?, file.u16()?);
+
+    let mut swatches = vec![];
+    for _ in 0..file.u32()? {
+        let block_type = file.u16()?;
+        let length = file.u32()? as usize;
+        let mut block = AseReader { bytes: file.take(length)? };
+        if block_type != ASE_COLOR {
+            continue;
+        }
+
+        let name_length = block.u16()? as usize;
+        let name_units: Vec<u16> = block.take(name_length * 2)?.chunks(2).map(|unit| u16::from_be_bytes([unit[0], unit[1]])).collect();
+        let name = String::from_utf16_lossy(&name_units).trim_end_matches('\0').to_string();
+
+        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let color = match block.take(4)? {
+            b"RGB " => Color32::from_rgb(channel(block.f32()?), channel(block.f32()?), channel(block.f32()?)),
+            b"Gray" => {
+                let gray = channel(block.f32()?);
+                Color32::from_rgb(gray, gray, gray)
+            },
+            b"CMYK" => {
+                let (c, m, y, k) = (block.f32()?, block.f32()?, block.f32()?, block.f32()?);
+                Color32::from_rgb(channel((1.0 - c) * (1.0 - k)), channel((1.0 - m) * (1.0 - k)), channel((1.0 - y) * (1.0 - k)))
+            },
+            model => {
+                print!("Skipping {} swatch {}: only RGB, CMYK and gray are supported", String::from_utf8_lossy(model).trim(), name);
+                continue;
+            },
+        };
+        swatches.push(Swatch { name, color });
+    }
+
+    Ok(swatches)
+}
+
+fn to_ase(swatches: &[Swatch]) -> Vec<u8> {
+    let mut ase = b"ASEF".to_vec();
+    ase.extend_from_slice(&1u16.to_be_bytes());
+    ase.extend_from_slice(&0u16.to_be_bytes());
+    ase.extend_from_slice(&(swatches.len() as u32).to_be_bytes());
+
+    for swatch in swatches {
+        let mut name: Vec<u16> = swatch.name.encode_utf16().collect();
+        name.push(0);
+
+        let mut block = (name.len() as u16).to_be_bytes().to_vec();
+        block.extend(name.iter().flat_map(|unit| unit.to_be_bytes()));
+        block.extend_from_slice(b"RGB ");
+        for channel in [swatch.color.r(), swatch.color.g(), swatch.color.b()] {
+            block.extend_from_slice(&(channel as f32 / 255.0).to_be_bytes());
+        }
+        block.extend_from_slice(&ASE_NORMAL.to_be_bytes());
+
+        ase.extend_from_slice(&ASE_COLOR.to_be_bytes());
+        ase.extend_from_slice(&(block.len() as u32).to_be_bytes());
+        ase.extend(block);
+    }
+
+    ase
+}
+
+/// Procreate colors are HSB, each from 0 to 1.
+#[derive(Serialize, Deserialize)]
+struct ProcreateSwatch {
+    hue: f32,
+    saturation: f32,
+    brightness: f32,
+    alpha: f32,
+    #[serde(rename = "colorSpace", default)]
+    color_space: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProcreatePalette {
+    name: String,
+
+    // Empty slots in the palette grid are null
+    swatches: Vec<Option<ProcreateSwatch>>,
+}
+
+fn parse_procreate(json: &str) -> io::Result<Vec<Swatch>> {
+    // Usually an array of one palette, but single palettes turn up too.
+    let palettes: Vec<ProcreatePalette> = serde_json::from_str(json)
+        .or_else(|_| serde_json::from_str(json).map(|palette| vec![palette]))?;
+
+    let mut swatches = vec![];
+    for palette in palettes {
+        for (i, swatch) in palette.swatches.iter().enumerate() {
+            if let Some(swatch) = swatch {
+                let [r, g, b] = color::hsb_to_rgb(swatch.hue, swatch.saturation, swatch.brightness);
+                swatches.push(Swatch { name: format!("{} {}", palette.name, i + 1), color: Color32::from_rgb(r, g, b) });
+            }
+        }
+    }
+
+    Ok(swatches)
+}
+
+fn to_procreate(name: &str, swatches: &[Swatch]) -> serde_json::Result<String> {
+    let palette = ProcreatePalette {
+        name: name.to_string(),
+        swatches: swatches.iter().map(|swatch| {
+            let [hue, saturation, brightness] = color::rgb_to_hsb(swatch.color);
+            Some(ProcreateSwatch { hue, saturation, brightness, alpha: 1.0, color_space: 0 })
+        }).collect(),
+    };
+
+    serde_json::to_string(&[palette])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swatches() -> Vec<Swatch> {
+        vec![
+            Swatch { name: "310 Black".to_string(), color: Color32::from_rgb(0, 0, 0) },
+            Swatch { name: "Rosé 🌹".to_string(), color: Color32::from_rgb(232, 135, 155) },
+            Swatch { name: "B5200 Snow White".to_string(), color: Color32::from_rgb(255, 255, 255) },
+            Swatch { name: "Teal".to_string(), color: Color32::from_rgb(31, 128, 117) },
+        ]
+    }
+
+    fn round_trip(format: Format) -> Vec<Swatch> {
+        let path = std::env::temp_dir().join(format!("cross-swatches-{}.{}", std::process::id(), format.extension()));
+        save(&path, "Test", &swatches()).expect("palette saves");
+        let loaded = load(&path);
+        _ = std::fs::remove_file(&path);
+        loaded.expect("palette loads")
+    }
+
+    fn colors(swatches: &[Swatch]) -> Vec<Color32> {
+        swatches.iter().map(|swatch| swatch.color).collect()
+    }
+
+    fn names(swatches: &[Swatch]) -> Vec<String> {
+        swatches.iter().map(|swatch| swatch.name.clone()).collect()
+    }
+
+    /// An .ase file holding the given (block type, block) pairs
+    fn ase_file(blocks: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut ase = b"ASEF\0\x01\0\0".to_vec();
+        ase.extend_from_slice(&(blocks.len() as u32).to_be_bytes());
+        for (block_type, block) in blocks {
+            ase.extend_from_slice(&block_type.to_be_bytes());
+            ase.extend_from_slice(&(block.len() as u32).to_be_bytes());
+            ase.extend_from_slice(block);
+        }
+
+        ase
+    }
+
+    fn ase_color(name: &str, model: &[u8; 4], values: &[f32]) -> Vec<u8> {
+        let name: Vec<u16> = name.encode_utf16().chain([0]).collect();
+        let mut block = (name.len() as u16).to_be_bytes().to_vec();
+        block.extend(name.iter().flat_map(|unit| unit.to_be_bytes()));
+        block.extend_from_slice(model);
+        block.extend(values.iter().flat_map(|value| value.to_be_bytes()));
+        block.extend_from_slice(&ASE_NORMAL.to_be_bytes());
+        block
+    }
+
+    #[test]
+    fn gpl_round_trips_names_and_colors() {
+        let loaded = round_trip(Format::Gpl);
+        assert_eq!(names(&loaded), names(&swatches()));
+        assert_eq!(colors(&loaded), colors(&swatches()));
+    }
+
+    #[test]
+    fn ase_round_trips_names_and_colors() {
+        let loaded = round_trip(Format::Ase);
+        assert_eq!(names(&loaded), names(&swatches()));
+        assert_eq!(colors(&loaded), colors(&swatches()));
+    }
+
+    #[test]
+    fn procreate_round_trips_colors() {
+        let loaded = round_trip(Format::Procreate);
+        assert_eq!(names(&loaded), ["Test 1", "Test 2", "Test 3", "Test 4"]);
+        assert_eq!(colors(&loaded), colors(&swatches()));
+    }
+
+    #[test]
+    fn ase_names_count_utf16_units_including_the_terminator() {
+        let ase = to_ase(&swatches()[1..2]);
+        // Header, one block's type and length, then the name length
+        assert_eq!(u16::from_be_bytes([ase[18], ase[19]]) as usize, "Rosé 🌹".encode_utf16().count() + 1);
+        assert_eq!(names(&parse_ase(&ase).unwrap()), ["Rosé 🌹"]);
+    }
+
+    #[test]
+    fn ase_converts_cmyk_and_gray_and_skips_other_blocks() {
+        let group_start = 0xc001;
+        let ase = ase_file(&[
+            (group_start, vec![0, 1, 0, 0]),
+            (ASE_COLOR, ase_color("Cyan", b"CMYK", &[1.0, 0.0, 0.0, 0.0])),
+            (ASE_COLOR, ase_color("Half black red", b"CMYK", &[0.0, 1.0, 1.0, 0.5])),
+            (ASE_COLOR, ase_color("Gray", b"Gray", &[0.5])),
+            (ASE_COLOR, ase_color("Lab", b"LAB ", &[50.0, 0.0, 0.0])),
+        ]);
+
+        let loaded = parse_ase(&ase).unwrap();
+        assert_eq!(names(&loaded), ["Cyan", "Half black red", "Gray"]);
+        assert_eq!(colors(&loaded), [Color32::from_rgb(0, 255, 255), Color32::from_rgb(128, 0, 0), Color32::from_rgb(128, 128, 128)]);
+    }
+
+    #[test]
+    fn ase_cut_short_is_an_error() {
+        let ase = to_ase(&swatches());
+        assert!(parse_ase(&ase[..ase.len() - 3]).is_err());
+        assert!(parse_ase(b"GIMP").is_err());
+    }
+
+    #[test]
+    fn procreate_reads_an_array_or_a_single_palette() {
+        let swatch = r#"{"hue": 0.0, "saturation": 1.0, "brightness": 1.0, "alpha": 1.0}"#;
+        let single = format!(r#"{{"name": "Reds", "swatches": [{}, null, {}]}}"#, swatch, swatch);
+        let array = format!("[{}]", single);
+
+        for json in [single, array] {
+            let loaded = parse_procreate(&json).unwrap();
+            assert_eq!(names(&loaded), ["Reds 1", "Reds 3"]);
+            assert_eq!(colors(&loaded), [Color32::from_rgb(255, 0, 0); 2]);
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! Long-lived analysis thread.
+//!
+//! Submitting a job supersedes anything queued or running, and rapid submissions (like dragging a slider)
+//! are debounced so only the last one runs. Results are tagged with their job id so stale charts are dropped.
+use crate::egui::ColorImage;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use super::analysis::{Cancellation, ColorCurve, Metrics, Pipeline, PrunedColor};
+use super::config::Config;
+use super::floss::Floss;
+use super::mask::Mask;
+use super::renderer::ChartData;
+
+// How long settings must stay unchanged before a job starts running.
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+#[derive(Clone, Copy)]
+enum JobKind {
+    Pattern,
+    ColorCurve,
+}
+
+struct Job {
+    id: u64,
+    kind: JobKind,
+    image: Arc<ColorImage>,
+    image_id: u64,
+    config: Config,
+
+    // Flosses the palette is limited to, if restricted to the stash
+    stash: Option<Vec<&'static Floss>>,
+
+    // Areas to prioritize, if any are marked
+    mask: Option<Mask>,
+}
+
+struct JobResult {
+    job_id: u64,
+    output: Output,
+}
+
+/// What a finished job produced. Each is None if there was nothing to analyze.
+pub enum Output {
+    Pattern(Option<(ChartData, Metrics, Vec<PrunedColor>)>),
+    ColorCurve(Option<ColorCurve>),
+}
+
+pub struct AnalysisWorker {
+    job_sender: mpsc::Sender<Job>,
+    result_receiver: mpsc::Receiver<JobResult>,
+
+    // Id of the most recently submitted job. Shared with the worker thread so running jobs can tell they're stale.
+    latest_job: Arc<AtomicU64>,
+    busy: bool,
+}
+
+impl Default for AnalysisWorker {
+    fn default() -> Self {
+        let (job_sender, job_receiver) = mpsc::channel();
+        let (result_sender, result_receiver) = mpsc::channel();
+        let latest_job = Arc::new(AtomicU64::new(0));
+
+        let worker_latest_job = latest_job.clone();
+        thread::spawn(move || run_worker(job_receiver, result_sender, worker_latest_job));
+
+        Self {
+            job_sender,
+            result_receiver,
+            latest_job,
+            busy: false,
+        }
+    }
+}
+
+impl AnalysisWorker {
+    /// Queues a new analysis, superseding any earlier job.
+    pub fn submit(&mut self, image: Arc<ColorImage>, image_id: u64, config: Config, stash: Option<Vec<&'static Floss>>, mask: Option<Mask>) {
+        self.send(JobKind::Pattern, image, image_id, config, stash, mask);
+    }
+
+    /// Queues a sweep over color counts, superseding any earlier job.
+    pub fn submit_color_curve(&mut self, image: Arc<ColorImage>, image_id: u64, config: Config, stash: Option<Vec<&'static Floss>>, mask: Option<Mask>) {
+        self.send(JobKind::ColorCurve, image, image_id, config, stash, mask);
+    }
+
+    fn send(&mut self, kind: JobKind, image: Arc<ColorImage>, image_id: u64, config: Config, stash: Option<Vec<&'static Floss>>, mask: Option<Mask>) {
+        let id = self.latest_job.fetch_add(1, Ordering::Relaxed) + 1;
+        self.busy = true;
+        _ = self.job_sender.send(Job { id, kind, image, image_id, config, stash, mask });
+    }
+
+    /// Supersedes any queued or running job without starting a new one.
+    pub fn cancel(&mut self) {
+        self.latest_job.fetch_add(1, Ordering::Relaxed);
+        self.busy = false;
+    }
+
+    /// True while the latest job has not yet produced a result.
+    pub fn is_busy(&self) -> bool {
+        self.busy
+    }
+
+    /// Returns the result of the latest job once it has finished. Results from superseded jobs are discarded.
+    pub fn poll(&mut self) -> Option<Output> {
+        let latest_job = self.latest_job.load(Ordering::Relaxed);
+        let mut latest_result = None;
+        for result in self.result_receiver.try_iter() {
+            if result.job_id == latest_job {
+                latest_result = Some(result.output);
+            }
+        }
+
+        if latest_result.is_some() {
+            self.busy = false;
+        }
+
+        latest_result
+    }
+}
+
+fn run_worker(jobs: mpsc::Receiver<Job>, results: mpsc::Sender<JobResult>, latest_job: Arc<AtomicU64>) {
+    // Owned by this thread, so cached stages carry over from one job to the next.
+    let mut pipeline = Pipeline::default();
+
+    // The loops end once the UI drops its side of either channel.
+    while let Ok(mut job) = jobs.recv() {
+        // Keep taking newer jobs until submissions pause.
+        loop {
+            match jobs.recv_timeout(DEBOUNCE) {
+                Ok(newer_job) => job = newer_job,
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+        }
+
+        let cancellation = Cancellation::new(latest_job.clone(), job.id);
+        if cancellation.is_cancelled() {
+            continue;
+        }
+
+        let (stash, mask) = (job.stash.as_deref(), job.mask.as_ref());
+        let output = match job.kind {
+            JobKind::Pattern => Output::Pattern(pipeline.update_pattern(&job.image, job.image_id, &job.config, stash, mask, &cancellation)),
+            JobKind::ColorCurve => Output::ColorCurve(pipeline.color_curve(&job.image, job.image_id, &job.config, stash, mask, &cancellation)),
+        };
+
+        // A cancelled job returns None too, but it is never the latest so the UI ignores it.
+        if results.send(JobResult { job_id: job.id, output }).is_err() {
+            return;
+        }
+    }
+}
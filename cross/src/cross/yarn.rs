@@ -0,0 +1,37 @@
+//! Generic yarn colors for knitting charts. They aren't any brand's shades and have no shade numbers to order by.
+use super::floss::Floss;
+
+// Each color goes by its name, which stands in for a code wherever one is asked for.
+const fn shade(name: &'static str, r: u8, g: u8, b: u8) -> Floss {
+    Floss { brand: "Generic", code: name, name, rgb: [r, g, b] }
+}
+
+// A general range of fingering-weight wool colors. Match them to your yarn's shade card by name and color.
+pub const YARN: &[Floss] = &[
+    shade("Natural White", 240, 236, 226),
+    shade("Oatmeal", 214, 200, 176),
+    shade("Light Grey", 190, 190, 188),
+    shade("Mid Grey", 140, 140, 138),
+    shade("Charcoal", 70, 70, 72),
+    shade("Black", 28, 28, 30),
+    shade("Fawn", 176, 140, 104),
+    shade("Chocolate", 92, 62, 44),
+    shade("Scarlet", 196, 36, 40),
+    shade("Burgundy", 120, 28, 46),
+    shade("Rose", 222, 140, 150),
+    shade("Pale Pink", 240, 200, 204),
+    shade("Orange", 230, 120, 40),
+    shade("Mustard", 206, 160, 40),
+    shade("Lemon", 244, 220, 90),
+    shade("Moss", 110, 120, 60),
+    shade("Leaf Green", 70, 140, 70),
+    shade("Bottle Green", 30, 80, 56),
+    shade("Teal", 30, 120, 124),
+    shade("Sky Blue", 140, 190, 226),
+    shade("Cornflower", 90, 120, 200),
+    shade("Royal Blue", 40, 70, 160),
+    shade("Navy", 30, 36, 70),
+    shade("Lilac", 180, 160, 206),
+    shade("Purple", 100, 54, 120),
+    shade("Heather", 150, 110, 140),
+];